@@ -0,0 +1,194 @@
+use std::iter;
+
+use viewtris::{
+    action::{Action, ActionKind},
+    tables::{damage_table, kick_table::SRS},
+    tetromino::{Cell, Mino, MinoVariant, Spin},
+};
+
+use crate::rng::PieceQueue;
+
+/// The width of the board
+pub const COLUMNS: usize = 10;
+/// The height of the board, including the rows hidden above the visible 20
+pub const ROWS: usize = 40;
+
+/// Holds the state of a jstris board. As with the board of `tetrio-replay`, commands issued to the
+/// board are turned into the [ActionKind]s they result in. Since jstris records the effects of
+/// gravity and auto-shift as actions of their own, the board has no notion of time.
+pub struct Board {
+    /// The cells of pieces which have locked, from the bottom row up
+    matrix: Vec<[Cell; COLUMNS]>,
+    queue: PieceQueue,
+    pub active: Mino,
+    hold: Option<MinoVariant>,
+    /// Whether hold has been used since the last piece locked
+    held: bool,
+}
+
+impl Board {
+    pub fn new(mut queue: PieceQueue) -> (Self, Vec<Action>) {
+        let active = queue.pop().into();
+        (
+            Self {
+                matrix: vec![[Cell::Empty; COLUMNS]; ROWS],
+                queue,
+                active,
+                hold: None,
+                held: false,
+            },
+            vec![ActionKind::Reposition { piece: active }.attach_frame(0)],
+        )
+    }
+
+    /// Tests whether the mino is within the board and clear of every filled cell
+    fn fits(&self, mino: &Mino) -> bool {
+        mino.position().0.iter().all(|&(x, y)| {
+            (0..COLUMNS as isize).contains(&x)
+                && (0..ROWS as isize).contains(&y)
+                && self.matrix[y as usize][x as usize].is_empty()
+        })
+    }
+
+    fn reposition(&mut self, to: Mino) -> Vec<ActionKind> {
+        if to == self.active {
+            return vec![];
+        }
+        self.active = to;
+        vec![ActionKind::Reposition { piece: to }]
+    }
+
+    /// Moves the active piece by `offset` for as long as it fits
+    fn slide(&mut self, offset: (i16, i16), limit: usize) -> Vec<ActionKind> {
+        let to = iter::successors(Some(self.active), |mino| {
+            let mut next = *mino;
+            next.coord.0 += offset.0;
+            next.coord.1 += offset.1;
+            self.fits(&next).then_some(next)
+        })
+        .take(limit + 1)
+        .last()
+        .unwrap();
+        self.reposition(to)
+    }
+
+    /// Shifts the active piece by up to the given amount of columns
+    pub fn shift(&mut self, cells: i8) -> Vec<ActionKind> {
+        self.slide((cells.signum() as i16, 0), cells.unsigned_abs() as usize)
+    }
+
+    /// Moves the active piece down a row, if there is room
+    pub fn fall(&mut self) -> Vec<ActionKind> {
+        self.slide((0, -1), 1)
+    }
+
+    /// Moves the active piece as far down as it will go, without locking it
+    pub fn sonic_drop(&mut self) -> Vec<ActionKind> {
+        self.slide((0, -1), ROWS)
+    }
+
+    /// Rotates the active piece, trying the SRS kicks in order
+    pub fn rotate_active(&mut self, spin: Spin) -> Vec<ActionKind> {
+        let rotated = self.active.rotate(spin);
+        let kicks = self.active.kick_in(&SRS, spin).cloned().unwrap_or_default();
+
+        iter::once((0, 0))
+            .chain(kicks)
+            .map(|(x, y)| {
+                let mut kicked = rotated;
+                kicked.coord.0 += x as i16;
+                kicked.coord.1 += y as i16;
+                kicked
+            })
+            .find(|kicked| self.fits(kicked))
+            .map(|kicked| self.reposition(kicked))
+            .unwrap_or_default()
+    }
+
+    pub fn hold(&mut self) -> Vec<ActionKind> {
+        if self.held {
+            return vec![];
+        }
+        self.held = true;
+        let next = match self.hold {
+            Some(held) => held,
+            None => self.queue.pop(),
+        };
+        self.hold = Some(self.active.variant);
+        self.active = next.into();
+        vec![
+            ActionKind::Hold,
+            ActionKind::Reposition { piece: self.active },
+        ]
+    }
+
+    /// Replaces the active piece with a new one at the top of the board
+    pub fn set_active(&mut self, variant: MinoVariant) -> Vec<ActionKind> {
+        self.reposition(variant.into())
+    }
+
+    /// Locks the active piece where it would land, clearing any lines it completes. Spins are not
+    /// detected, so the piece always locks without one.
+    pub fn drop_active(&mut self) -> Vec<ActionKind> {
+        let mut out = self.sonic_drop();
+        let kind = Cell::from(self.active.variant);
+        for (x, y) in self.active.position().0 {
+            self.matrix[y as usize][x as usize] = kind;
+            out.push(ActionKind::Cell {
+                position: (x as u8, y as u8),
+                kind,
+            });
+        }
+        out.push(ActionKind::Lock {
+            spin: damage_table::Spin::None,
+        });
+        out.extend(self.clear_lines());
+
+        self.held = false;
+        self.active = self.queue.pop().into();
+        out.push(ActionKind::Reposition { piece: self.active });
+        out
+    }
+
+    /// Removes every full row. As with `tetrio-replay`, each cleared row is given as it is numbered
+    /// after the rows below it have been cleared.
+    fn clear_lines(&mut self) -> Vec<ActionKind> {
+        let mut out = vec![];
+        let mut row = 0;
+        while row < self.matrix.len() {
+            if self.matrix[row].iter().all(|cell| !cell.is_empty()) {
+                self.matrix.remove(row);
+                self.matrix.push([Cell::Empty; COLUMNS]);
+                out.push(ActionKind::LineClear { row: row as u8 });
+            } else {
+                row += 1;
+            }
+        }
+        out
+    }
+
+    /// Pushes the board up by `lines` rows of garbage, with a hole in `column` if there is one
+    pub fn add_garbage(&mut self, lines: u8, column: Option<u8>) -> Vec<ActionKind> {
+        let hole = column.unwrap_or(0);
+        self.matrix.rotate_right(lines as usize);
+        for row in &mut self.matrix[..lines as usize] {
+            row.fill(Cell::Garbage);
+            if column.is_some() {
+                row[hole as usize] = Cell::Empty;
+            }
+        }
+
+        let mut out = vec![ActionKind::Garbage {
+            column: hole as u16,
+            height: lines as u16,
+        }];
+        // solid garbage is made by filling in the holes
+        if column.is_none() {
+            out.extend((0..lines).map(|row| ActionKind::Cell {
+                position: (hole, row),
+                kind: Cell::Garbage,
+            }));
+        }
+        out
+    }
+}
@@ -0,0 +1,9 @@
+mod board;
+pub mod lz_string;
+mod reconstruct;
+mod replay;
+mod rng;
+
+pub use reconstruct::reconstruct;
+pub use replay::*;
+pub use viewtris;
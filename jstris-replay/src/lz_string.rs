@@ -0,0 +1,223 @@
+//! The `compressToEncodedURIComponent` and `decompressFromEncodedURIComponent` functions of the
+//! lz-string javascript library, which jstris uses to fit replays into links.
+//!
+//! Strings are handled as javascript sees them, as UTF-16 code units.
+
+use std::collections::{HashMap, HashSet};
+
+/// The characters the compressed string is written with, each standing for six bits
+const URI_SAFE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+-";
+
+/// The number of bits held by each character of the compressed string
+const BITS_PER_CHAR: u32 = 6;
+
+/// The dictionary codes which have a meaning of their own
+const CHAR_8_BITS: u32 = 0;
+const CHAR_16_BITS: u32 = 1;
+const END_OF_STREAM: u32 = 2;
+
+struct BitWriter {
+    out: String,
+    value: u8,
+    position: u32,
+}
+
+impl BitWriter {
+    /// Writes the lowest `bits` bits of `value`, least significant first
+    fn write(&mut self, mut value: u32, bits: u32) {
+        for _ in 0..bits {
+            self.value = (self.value << 1) | (value & 1) as u8;
+            if self.position == BITS_PER_CHAR - 1 {
+                self.out.push(URI_SAFE[self.value as usize] as char);
+                self.position = 0;
+                self.value = 0;
+            } else {
+                self.position += 1;
+            }
+            value >>= 1;
+        }
+    }
+
+    /// Pads the last character with zeroes
+    fn finish(mut self) -> String {
+        loop {
+            self.value <<= 1;
+            if self.position == BITS_PER_CHAR - 1 {
+                self.out.push(URI_SAFE[self.value as usize] as char);
+                return self.out;
+            }
+            self.position += 1;
+        }
+    }
+}
+
+struct BitReader<I> {
+    chars: I,
+    value: u8,
+    /// The bit of `value` to be read next, which is zero once every bit has been read
+    mask: u8,
+}
+
+impl<I: Iterator<Item = u8>> BitReader<I> {
+    /// Reads `bits` bits, least significant first. Gives `None` if the input runs out.
+    fn read(&mut self, bits: u32) -> Option<u32> {
+        let mut out = 0;
+        for bit in 0..bits {
+            if self.mask == 0 {
+                self.value = self.chars.next()?;
+                self.mask = 1 << (BITS_PER_CHAR - 1);
+            }
+            if self.value & self.mask != 0 {
+                out |= 1 << bit;
+            }
+            self.mask >>= 1;
+        }
+        Some(out)
+    }
+}
+
+/// The state of the compressor while it works through a string
+struct Compressor {
+    writer: BitWriter,
+    dictionary: HashMap<Vec<u16>, u32>,
+    /// Characters in the dictionary which have not been written out yet
+    to_create: HashSet<u16>,
+    enlarge_in: u32,
+    num_bits: u32,
+}
+
+impl Compressor {
+    /// Counts down to the point where codes need another bit
+    fn grow(&mut self) {
+        self.enlarge_in -= 1;
+        if self.enlarge_in == 0 {
+            self.enlarge_in = 1 << self.num_bits;
+            self.num_bits += 1;
+        }
+    }
+
+    /// Adds a string to the dictionary under the next free code
+    fn define(&mut self, string: Vec<u16>) {
+        // the first three codes are kept for the codes with a meaning of their own
+        let code = self.dictionary.len() as u32 + 3;
+        self.dictionary.insert(string, code);
+    }
+
+    /// Writes the code of `w`, writing out the character itself first if it is new
+    fn emit(&mut self, w: &[u16]) {
+        if w.len() == 1 && self.to_create.remove(&w[0]) {
+            if w[0] < 256 {
+                self.writer.write(CHAR_8_BITS, self.num_bits);
+                self.writer.write(w[0] as u32, 8);
+            } else {
+                self.writer.write(CHAR_16_BITS, self.num_bits);
+                self.writer.write(w[0] as u32, 16);
+            }
+            self.grow();
+        } else {
+            self.writer.write(self.dictionary[w], self.num_bits);
+        }
+        self.grow();
+    }
+}
+
+/// Compresses a string into one made up only of characters which are safe to put in a URI
+pub fn compress(input: &str) -> String {
+    let mut compressor = Compressor {
+        writer: BitWriter {
+            out: String::new(),
+            value: 0,
+            position: 0,
+        },
+        dictionary: HashMap::new(),
+        to_create: HashSet::new(),
+        enlarge_in: 2,
+        num_bits: 2,
+    };
+    let mut w: Vec<u16> = Vec::new();
+
+    for c in input.encode_utf16() {
+        if !compressor.dictionary.contains_key(&[c][..]) {
+            compressor.define(vec![c]);
+            compressor.to_create.insert(c);
+        }
+        let mut wc = w.clone();
+        wc.push(c);
+        if compressor.dictionary.contains_key(&wc) {
+            w = wc;
+        } else {
+            compressor.emit(&w);
+            compressor.define(wc);
+            w = vec![c];
+        }
+    }
+    if !w.is_empty() {
+        compressor.emit(&w);
+    }
+
+    compressor.writer.write(END_OF_STREAM, compressor.num_bits);
+    compressor.writer.finish()
+}
+
+/// Decompresses a string made by [compress]. Gives `None` if the string was not made that way.
+pub fn decompress(input: &str) -> Option<String> {
+    // spaces are what become of `+` if the string is decoded as part of a URI
+    let chars = input
+        .bytes()
+        .map(|c| if c == b' ' { b'+' } else { c })
+        .map(|c| URI_SAFE.iter().position(|&safe| safe == c).map(|i| i as u8))
+        .collect::<Option<Vec<_>>>()?;
+    let mut reader = BitReader {
+        chars: chars.into_iter(),
+        value: 0,
+        mask: 0,
+    };
+
+    // the first three codes are kept for the codes with a meaning of their own
+    let mut dictionary: Vec<Vec<u16>> = vec![Vec::new(); 3];
+    let mut enlarge_in: u32 = 4;
+    let mut num_bits = 3;
+
+    let first = match reader.read(2)? {
+        CHAR_8_BITS => reader.read(8)? as u16,
+        CHAR_16_BITS => reader.read(16)? as u16,
+        _ => return Some(String::new()),
+    };
+    let mut w = vec![first];
+    dictionary.push(w.clone());
+    let mut out = w.clone();
+
+    loop {
+        let mut code = reader.read(num_bits)? as usize;
+        match code as u32 {
+            CHAR_8_BITS | CHAR_16_BITS => {
+                let bits = if code as u32 == CHAR_8_BITS { 8 } else { 16 };
+                dictionary.push(vec![reader.read(bits)? as u16]);
+                code = dictionary.len() - 1;
+                enlarge_in -= 1;
+            }
+            END_OF_STREAM => return String::from_utf16(&out).ok(),
+            _ => (),
+        }
+        if enlarge_in == 0 {
+            enlarge_in = 1 << num_bits;
+            num_bits += 1;
+        }
+
+        let entry = match dictionary.get(code) {
+            Some(entry) => entry.clone(),
+            // the code being defined by this very entry
+            None if code == dictionary.len() => [&w[..], &w[..1]].concat(),
+            None => return None,
+        };
+        out.extend_from_slice(&entry);
+        dictionary.push([&w[..], &entry[..1]].concat());
+        enlarge_in -= 1;
+        w = entry;
+
+        if enlarge_in == 0 {
+            enlarge_in = 1 << num_bits;
+            num_bits += 1;
+        }
+    }
+}
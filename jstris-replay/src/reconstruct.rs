@@ -0,0 +1,83 @@
+use viewtris::{action::Action, tetromino::Spin};
+
+use crate::{
+    board::{Board, COLUMNS, ROWS},
+    replay::{Replay, ReplayAction},
+    rng::PieceQueue,
+};
+
+/// Plays back a jstris replay, producing the same kind of action stream as `tetrio-replay` does
+/// for tetrio replays.
+///
+/// Only games using the 7-bag randomizer on an empty board can be played back.
+pub fn reconstruct(replay: &Replay) -> Result<Vec<Action>, String> {
+    let config = &replay.config;
+    if config.randomizer != 0 {
+        return Err(format!(
+            "randomizer {} is not supported, only the 7-bag (0) is",
+            config.randomizer
+        ));
+    }
+
+    let (mut board, mut stream) = Board::new(PieceQueue::seeded(&config.seed));
+    let mut soft_dropping = false;
+    // the direction auto-repeat moves in, which is the direction of the last shift
+    let mut shift_direction = 1;
+
+    for timed in &replay.actions {
+        let mut kinds = match timed.action {
+            ReplayAction::MoveLeft => {
+                shift_direction = -1;
+                board.shift(-1)
+            }
+            ReplayAction::MoveRight => {
+                shift_direction = 1;
+                board.shift(1)
+            }
+            ReplayAction::DasLeft => {
+                shift_direction = -1;
+                board.shift(-(COLUMNS as i8))
+            }
+            ReplayAction::DasRight => {
+                shift_direction = 1;
+                board.shift(COLUMNS as i8)
+            }
+            ReplayAction::ArrMove => board.shift(shift_direction),
+            ReplayAction::RotateLeft => board.rotate_active(Spin::CCW),
+            ReplayAction::RotateRight => board.rotate_active(Spin::CW),
+            ReplayAction::Rotate180 => board.rotate_active(Spin::Flip),
+            ReplayAction::HardDrop => board.drop_active(),
+            ReplayAction::SoftDrop => {
+                soft_dropping = !soft_dropping;
+                vec![]
+            }
+            ReplayAction::GravityStep => board.fall(),
+            ReplayAction::Hold => board.hold(),
+            ReplayAction::Garbage { lines, column } => {
+                if column as usize >= COLUMNS || lines as usize > ROWS {
+                    return Err(format!(
+                        "garbage of {lines} rows with a hole in column {column} does not fit on the \
+                         board, at {}ms",
+                        timed.time
+                    ));
+                }
+                board.add_garbage(lines, Some(column))
+            }
+            ReplayAction::SolidGarbage { lines } => board.add_garbage(lines.min(ROWS as u8), None),
+            ReplayAction::RedBar { .. } => vec![],
+            ReplayAction::SetPiece { piece } => board.set_active(piece),
+        };
+
+        // with instant soft drop, the piece stays on the floor for as long as soft drop is held
+        if soft_dropping && config.instant_soft_drop() {
+            kinds.extend(board.sonic_drop());
+        }
+        stream.extend(
+            kinds
+                .into_iter()
+                .map(|kind| kind.attach_frame(timed.frame())),
+        );
+    }
+
+    Ok(stream)
+}
@@ -0,0 +1,376 @@
+//! Reading the replays saved by jstris. A replay is a json object holding the configuration of the
+//! game (`c`) and its actions (`d`), which is compressed with [lz_string] so that it fits into a
+//! link. The actions are packed into 16 bit words and written in base64.
+//!
+//! Each word holds the time of the action in milliseconds in its upper 12 bits, and the kind of
+//! action in its lower 4 bits. Since 12 bits only count up to about four seconds, the time wraps
+//! around, which is noticed as the time going backwards; longer pauses are marked by an auxiliary
+//! action. Some kinds of action are followed by a word of their own describing them further.
+
+use std::{fmt::Display, str::FromStr};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+use viewtris::tetromino::MinoVariant;
+
+use crate::{lz_string, rng::PIECES};
+
+/// The number of milliseconds counted by the time of an action before it wraps around
+const TIME_WRAP: u32 = 1 << 12;
+
+/// The kinds of auxiliary action, as given in the upper 4 bits of the word following them
+const AUX_AFK: u16 = 0;
+const AUX_BLOCK_SET: u16 = 1;
+
+/// Why a replay could not be read
+#[derive(Debug)]
+pub enum Error {
+    /// The replay is neither json nor json compressed with [lz_string]
+    Unreadable,
+    Json(serde_json::Error),
+    Base64(base64::DecodeError),
+    /// The actions stop partway through an action
+    Truncated,
+    /// An auxiliary action is of a kind that is not known
+    UnknownAux {
+        kind: u16,
+    },
+    /// A piece is named by a number which does not belong to any piece
+    UnknownPiece {
+        id: u16,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Unreadable => write!(f, "the file is not a jstris replay"),
+            Error::Json(error) => write!(f, "{error}"),
+            Error::Base64(error) => write!(f, "the actions of the replay are not base64: {error}"),
+            Error::Truncated => write!(f, "the actions of the replay stop partway through"),
+            Error::UnknownAux { kind } => write!(f, "unknown auxiliary action `{kind}`"),
+            Error::UnknownPiece { id } => write!(f, "unknown piece `{id}`"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Json(error) => Some(error),
+            Error::Base64(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+impl From<base64::DecodeError> for Error {
+    fn from(error: base64::DecodeError) -> Self {
+        Self::Base64(error)
+    }
+}
+
+/// Reads the seed, which older replays may give as a number
+fn seed<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::String(seed) => seed,
+        other => other.to_string(),
+    })
+}
+
+/// The settings a game was played with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    /// The version of the replay format
+    #[serde(rename = "v")]
+    pub version: f64,
+    /// The seed the pieces are generated from
+    #[serde(deserialize_with = "seed")]
+    pub seed: String,
+    /// The game mode, such as sprint or cheese race
+    #[serde(rename = "m", default)]
+    pub mode: u32,
+    /// The randomizer the pieces are generated with, which is 0 for the 7-bag
+    #[serde(rename = "r", default)]
+    pub randomizer: u32,
+    /// How fast soft drop is, from slow (0) to instant (4)
+    #[serde(rename = "softDropId", default)]
+    pub soft_drop_id: u32,
+    /// The time the game started at, in milliseconds since the unix epoch
+    #[serde(rename = "gameStart", default)]
+    pub game_start: f64,
+    /// The time the game ended at, in milliseconds since the unix epoch
+    #[serde(rename = "gameEnd", default)]
+    pub game_end: f64,
+    /// Settings which are not needed to play back the replay, such as the skin
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl Config {
+    /// Whether pieces drop to the floor as soon as soft drop is pressed
+    pub fn instant_soft_drop(&self) -> bool {
+        self.soft_drop_id == 4
+    }
+}
+
+/// Something done by the player or to their board
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayAction {
+    MoveLeft,
+    MoveRight,
+    /// Auto-shifts the piece as far left as it will go
+    DasLeft,
+    /// Auto-shifts the piece as far right as it will go
+    DasRight,
+    RotateLeft,
+    RotateRight,
+    Rotate180,
+    /// Drops the piece, which is also how a piece locking by itself is recorded
+    HardDrop,
+    /// Starts soft dropping, or stops if already soft dropping. The rows fallen are recorded as
+    /// gravity steps, unless soft drop is instant.
+    SoftDrop,
+    /// Moves the piece down a row
+    GravityStep,
+    Hold,
+    /// Adds `lines` rows of garbage with a hole in `column`
+    Garbage {
+        lines: u8,
+        column: u8,
+    },
+    /// Adds `lines` rows of garbage without any holes
+    SolidGarbage {
+        lines: u8,
+    },
+    /// Sets the amount of incoming garbage, which does not change the board
+    RedBar {
+        lines: u16,
+    },
+    /// Moves the piece a column further in the direction of the last shift
+    ArrMove,
+    /// Replaces the active piece
+    SetPiece {
+        piece: MinoVariant,
+    },
+}
+
+impl ReplayAction {
+    fn code(self) -> u16 {
+        match self {
+            ReplayAction::MoveLeft => 0,
+            ReplayAction::MoveRight => 1,
+            ReplayAction::DasLeft => 2,
+            ReplayAction::DasRight => 3,
+            ReplayAction::RotateLeft => 4,
+            ReplayAction::RotateRight => 5,
+            ReplayAction::Rotate180 => 6,
+            ReplayAction::HardDrop => 7,
+            ReplayAction::SoftDrop => 8,
+            ReplayAction::GravityStep => 9,
+            ReplayAction::Hold => 10,
+            ReplayAction::Garbage { .. } => 11,
+            ReplayAction::SolidGarbage { .. } => 12,
+            ReplayAction::RedBar { .. } => 13,
+            ReplayAction::ArrMove => 14,
+            ReplayAction::SetPiece { .. } => 15,
+        }
+    }
+
+    /// The word following the action, if it has one
+    fn payload(self) -> Option<u16> {
+        match self {
+            ReplayAction::Garbage { lines, column } => Some((lines as u16) << 8 | column as u16),
+            ReplayAction::SolidGarbage { lines } => Some(lines as u16),
+            ReplayAction::RedBar { lines } => Some(lines),
+            ReplayAction::SetPiece { piece } => {
+                let id = PIECES.iter().position(|&p| p == piece).unwrap() as u16;
+                Some(AUX_BLOCK_SET << 12 | id)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// An action and the time it happened at, in milliseconds since the start of the game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedAction {
+    pub time: u32,
+    pub action: ReplayAction,
+}
+
+impl TimedAction {
+    /// The frame the action happened on, with the game running at 60 frames per second
+    pub fn frame(&self) -> u32 {
+        (self.time as u64 * 60 / 1000) as u32
+    }
+}
+
+/// A replay of a single jstris game
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub config: Config,
+    pub actions: Vec<TimedAction>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RawReplay {
+    c: Config,
+    d: String,
+}
+
+/// Keeps track of the time while actions are read, since it wraps around
+#[derive(Default)]
+struct Clock {
+    /// The time at which the time of the last action last wrapped around
+    base: u32,
+    last: u32,
+}
+
+impl Clock {
+    fn tick(&mut self, time: u32) -> u32 {
+        if time < self.last {
+            self.base += TIME_WRAP;
+        }
+        self.last = time;
+        self.base + time
+    }
+
+    /// Skips ahead by `periods` times the length of time before the time wraps around
+    fn skip(&mut self, periods: u32) {
+        self.base += periods * TIME_WRAP;
+        self.last = 0;
+    }
+}
+
+fn decode_actions(bytes: &[u8]) -> Result<Vec<TimedAction>, Error> {
+    if !bytes.len().is_multiple_of(2) {
+        return Err(Error::Truncated);
+    }
+    let mut words = bytes
+        .chunks(2)
+        .map(|word| u16::from_be_bytes([word[0], word[1]]));
+    let mut clock = Clock::default();
+    let mut actions = Vec::new();
+
+    while let Some(word) = words.next() {
+        let time = clock.tick((word >> 4) as u32);
+        let mut payload = || words.next().ok_or(Error::Truncated);
+        let action = match word & 0xf {
+            0 => ReplayAction::MoveLeft,
+            1 => ReplayAction::MoveRight,
+            2 => ReplayAction::DasLeft,
+            3 => ReplayAction::DasRight,
+            4 => ReplayAction::RotateLeft,
+            5 => ReplayAction::RotateRight,
+            6 => ReplayAction::Rotate180,
+            7 => ReplayAction::HardDrop,
+            8 => ReplayAction::SoftDrop,
+            9 => ReplayAction::GravityStep,
+            10 => ReplayAction::Hold,
+            11 => {
+                let payload = payload()?;
+                ReplayAction::Garbage {
+                    lines: (payload >> 8) as u8,
+                    column: payload as u8,
+                }
+            }
+            12 => ReplayAction::SolidGarbage {
+                lines: payload()? as u8,
+            },
+            13 => ReplayAction::RedBar { lines: payload()? },
+            14 => ReplayAction::ArrMove,
+            _ => {
+                let payload = payload()?;
+                match payload >> 12 {
+                    AUX_AFK => {
+                        clock.skip((payload & 0xfff) as u32);
+                        continue;
+                    }
+                    AUX_BLOCK_SET => {
+                        let id = payload & 0xfff;
+                        ReplayAction::SetPiece {
+                            piece: *PIECES.get(id as usize).ok_or(Error::UnknownPiece { id })?,
+                        }
+                    }
+                    kind => return Err(Error::UnknownAux { kind }),
+                }
+            }
+        };
+        actions.push(TimedAction { time, action });
+    }
+
+    Ok(actions)
+}
+
+fn encode_actions(actions: &[TimedAction]) -> Vec<u8> {
+    let mut words = Vec::new();
+    let mut clock = Clock::default();
+
+    for &TimedAction { time, action } in actions {
+        let wraps = time / TIME_WRAP - clock.base / TIME_WRAP;
+        let low = time % TIME_WRAP;
+        // the time only wraps around by itself if it goes backwards, and only once
+        let natural = (wraps == 1 && low < clock.last) as u32;
+        if wraps > natural {
+            words.push((clock.last as u16) << 4 | 15);
+            words.push(AUX_AFK << 12 | (wraps - natural) as u16);
+            clock.skip(wraps - natural);
+        }
+        clock.tick(low);
+
+        words.push((low as u16) << 4 | action.code());
+        words.extend(action.payload());
+    }
+
+    words.into_iter().flat_map(u16::to_be_bytes).collect()
+}
+
+impl Replay {
+    /// Reads a replay from the contents of a file, which may hold the replay compressed (as found
+    /// in links to replays) or as json
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, Error> {
+        std::str::from_utf8(bytes)
+            .map_err(|_| Error::Unreadable)?
+            .parse()
+    }
+
+    /// Writes the replay in the compressed form found in links to replays
+    pub fn to_compressed(&self) -> String {
+        lz_string::compress(&self.to_json())
+    }
+
+    /// Writes the replay as json
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&RawReplay {
+            c: self.config.clone(),
+            d: STANDARD.encode(encode_actions(&self.actions)),
+        })
+        .unwrap()
+    }
+}
+
+impl FromStr for Replay {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let json = if s.starts_with('{') {
+            s.to_owned()
+        } else {
+            lz_string::decompress(s).ok_or(Error::Unreadable)?
+        };
+        let RawReplay { c, d } = serde_json::from_str(&json)?;
+        Ok(Replay {
+            config: c,
+            actions: decode_actions(&STANDARD.decode(d)?)?,
+        })
+    }
+}
@@ -0,0 +1,134 @@
+use viewtris::tetromino::MinoVariant;
+
+/// The pieces in the order jstris numbers them
+pub const PIECES: [MinoVariant; 7] = {
+    use MinoVariant::*;
+    [I, O, T, L, J, S, Z]
+};
+
+/// Converts a number to an unsigned 32 bit integer the way javascript's `>>> 0` does
+fn to_u32(n: f64) -> u32 {
+    n.trunc().rem_euclid(4294967296.0) as u32
+}
+
+/// The hash used to turn a seed of any length into the state of [Alea]
+struct Mash {
+    n: f64,
+}
+
+impl Mash {
+    fn new() -> Self {
+        Self {
+            n: 0xefc8249d_u32 as f64,
+        }
+    }
+
+    fn mash(&mut self, data: &str) -> f64 {
+        for code_unit in data.encode_utf16() {
+            self.n += code_unit as f64;
+            let mut h = 0.02519603282416938 * self.n;
+            self.n = to_u32(h) as f64;
+            h -= self.n;
+            h *= self.n;
+            self.n = to_u32(h) as f64;
+            h -= self.n;
+            self.n += h * 4294967296.0;
+        }
+        to_u32(self.n) as f64 * 2.3283064365386963e-10
+    }
+}
+
+/// The Alea RNG by Johannes Baagøe, which jstris seeds with the seed of each replay
+pub struct Alea {
+    s0: f64,
+    s1: f64,
+    s2: f64,
+    c: f64,
+}
+
+impl Alea {
+    pub fn seeded(seed: &str) -> Self {
+        let mut mash = Mash::new();
+        let mut rng = Self {
+            s0: mash.mash(" "),
+            s1: mash.mash(" "),
+            s2: mash.mash(" "),
+            c: 1.,
+        };
+        for s in [&mut rng.s0, &mut rng.s1, &mut rng.s2] {
+            *s -= mash.mash(seed);
+            if *s < 0. {
+                *s += 1.;
+            }
+        }
+        rng
+    }
+
+    /// Gives a number from 0 (inclusive) to 1 (exclusive)
+    pub fn next_float(&mut self) -> f64 {
+        let t = 2091639. * self.s0 + self.c * 2.3283064365386963e-10;
+        self.s0 = self.s1;
+        self.s1 = self.s2;
+        self.c = t.trunc();
+        self.s2 = t - self.c;
+        self.s2
+    }
+}
+
+/// The 7-bag randomizer of jstris, which draws each piece at random from the pieces left in the
+/// bag rather than shuffling the bag when it is filled
+pub struct PieceQueue {
+    rng: Alea,
+    bag: Vec<MinoVariant>,
+}
+
+impl PieceQueue {
+    pub fn seeded(seed: &str) -> Self {
+        Self {
+            rng: Alea::seeded(seed),
+            bag: Vec::new(),
+        }
+    }
+
+    pub fn pop(&mut self) -> MinoVariant {
+        if self.bag.is_empty() {
+            self.bag = PIECES.to_vec();
+        }
+        let drawn = (self.rng.next_float() * self.bag.len() as f64).floor() as usize;
+        self.bag.remove(drawn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use viewtris::tetromino::MinoVariant;
+
+    use super::{Alea, PieceQueue};
+
+    #[test]
+    fn alea() {
+        // as given by the javascript implementation
+        let mut rng = Alea::seeded("hello");
+        assert_eq!(rng.next_float(), 0.8750656815245748);
+        assert_eq!(rng.next_float(), 0.33650841237977147);
+        assert_eq!(rng.next_float(), 0.6642807484604418);
+
+        let mut rng = Alea::seeded("é✓");
+        assert_eq!(rng.next_float(), 0.21634470392018557);
+        assert_eq!(rng.next_float(), 0.30978222214616835);
+        assert_eq!(rng.next_float(), 0.1601861745584756);
+    }
+
+    #[test]
+    fn bags() {
+        use MinoVariant::*;
+
+        let mut queue = PieceQueue::seeded("6k2t9s");
+        let pieces = (0..14).map(|_| queue.pop()).collect::<Vec<_>>();
+        assert_eq!(pieces, [I, O, T, L, J, S, Z, J, I, Z, S, L, O, T]);
+
+        let mut queue = PieceQueue::seeded("hello");
+        let pieces = (0..14).map(|_| queue.pop()).collect::<Vec<_>>();
+        assert_eq!(pieces, [Z, T, J, L, I, S, O, L, T, J, O, I, S, Z]);
+    }
+}
@@ -0,0 +1,136 @@
+use jstris_replay::{lz_string, reconstruct, Config, Replay, ReplayAction, TimedAction};
+use viewtris::{
+    action::{Action, ActionKind},
+    tetromino::{Cell, MinoVariant},
+};
+
+fn replay(actions: &[(u32, ReplayAction)]) -> Replay {
+    let config = serde_json::from_value::<Config>(serde_json::json!({
+        "v": 3.3,
+        "seed": "6k2t9s",
+        "m": 1,
+        "r": 0,
+        "softDropId": 2,
+        "gameStart": 1700000000000u64,
+        "gameEnd": 1700000030000u64,
+        "bs": 0,
+    }))
+    .unwrap();
+    Replay {
+        config,
+        actions: actions
+            .iter()
+            .map(|&(time, action)| TimedAction { time, action })
+            .collect(),
+    }
+}
+
+#[test]
+fn lz_string() {
+    // as given by the javascript library
+    let samples = [
+        ("Hello, world", "BIUwNmD2A0AEDukBOYAmQ"),
+        ("abababababababab", "IYI17SKA"),
+        (
+            r#"{"c":{"v":3.3,"seed":"abc"},"d":"AAA="}"#,
+            "N4IgxiBcoG5QzAOngGhAZwKaYCZRAIYBGEAvmnpCAIK0C8IpQA",
+        ),
+        ("ünïcødé ✓", "D8Ow9wxgHwJglwAkMjkQ"),
+    ];
+    for (text, compressed) in samples {
+        assert_eq!(lz_string::compress(text), compressed);
+        assert_eq!(lz_string::decompress(compressed).as_deref(), Some(text));
+    }
+    assert_eq!(lz_string::decompress("not compressed!"), None);
+}
+
+#[test]
+fn round_trip() {
+    let original = replay(&[
+        (0, ReplayAction::MoveLeft),
+        (16, ReplayAction::DasRight),
+        (
+            3000,
+            ReplayAction::Garbage {
+                lines: 3,
+                column: 7,
+            },
+        ),
+        // wraps around by itself
+        (4100, ReplayAction::HardDrop),
+        // needs to be told it wrapped around
+        (
+            4200,
+            ReplayAction::SetPiece {
+                piece: MinoVariant::S,
+            },
+        ),
+        (20000, ReplayAction::SolidGarbage { lines: 2 }),
+        (20000, ReplayAction::RedBar { lines: 6 }),
+        (20001, ReplayAction::SoftDrop),
+    ]);
+
+    for written in [original.to_compressed(), original.to_json()] {
+        let read = written.parse::<Replay>().unwrap();
+        assert_eq!(read.actions, original.actions);
+        assert_eq!(read.config.seed, "6k2t9s");
+        assert_eq!(read.config.other["bs"], 0);
+    }
+}
+
+/// The actions which change the board, as opposed to moving the active piece
+fn placements(actions: &[Action]) -> Vec<String> {
+    actions
+        .iter()
+        .filter(|action| !matches!(action.kind, ActionKind::Reposition { .. }))
+        .map(|action| format!("{} {:?}", action.frame, action.kind))
+        .collect()
+}
+
+#[test]
+fn play_back() {
+    let actions = reconstruct(&replay(&[
+        (
+            0,
+            ReplayAction::Garbage {
+                lines: 1,
+                column: 0,
+            },
+        ),
+        (100, ReplayAction::RotateRight),
+        (200, ReplayAction::DasLeft),
+        (500, ReplayAction::HardDrop),
+        (600, ReplayAction::Hold),
+        (700, ReplayAction::Hold),
+        (1000, ReplayAction::HardDrop),
+    ]))
+    .unwrap();
+
+    // the pieces dealt are I, O, T
+    let i = Cell::Tetromino(MinoVariant::I);
+    let t = Cell::Tetromino(MinoVariant::T);
+    let expected = [
+        "0 Garbage { column: 0, height: 1 }".to_owned(),
+        format!("30 Cell {{ position: (0, 3), kind: {i:?} }}"),
+        format!("30 Cell {{ position: (0, 2), kind: {i:?} }}"),
+        format!("30 Cell {{ position: (0, 1), kind: {i:?} }}"),
+        format!("30 Cell {{ position: (0, 0), kind: {i:?} }}"),
+        "30 Lock { spin: None }".to_owned(),
+        "30 LineClear { row: 0 }".to_owned(),
+        // only the first hold goes through
+        "36 Hold".to_owned(),
+        format!("60 Cell {{ position: (3, 0), kind: {t:?} }}"),
+        format!("60 Cell {{ position: (4, 0), kind: {t:?} }}"),
+        format!("60 Cell {{ position: (5, 0), kind: {t:?} }}"),
+        format!("60 Cell {{ position: (4, 1), kind: {t:?} }}"),
+        "60 Lock { spin: None }".to_owned(),
+    ];
+    assert_eq!(placements(&actions), expected);
+}
+
+#[test]
+fn unsupported_randomizer() {
+    let mut replay = replay(&[]);
+    replay.config.randomizer = 1;
+    assert!(reconstruct(&replay).is_err());
+}
@@ -1,10 +1,13 @@
-use std::{collections::VecDeque, iter};
+use std::{borrow::Cow, collections::VecDeque, iter};
 
 use gridly::prelude::{Column, Grid, GridBounds, GridMut, Row};
 use if_chain::if_chain;
-use itertools::{Either, Itertools};
+use itertools::Itertools;
 use tap::Tap;
-use ttrm::{event::InteractionData, GameType};
+use ttrm::{
+    event::{self, Game, GarbageBlocking, Interaction, InteractionData, SpinBonuses, Stats},
+    GameType,
+};
 
 use crate::{
     reconstruct::{ShiftDirection, State},
@@ -13,11 +16,13 @@ use crate::{
 use viewtris::{
     action::{Action, ActionKind},
     positions::Positions,
+    tables::damage_table::{self, AttackKey},
     tetromino::{Cell, Mino, MinoVariant, Spin},
 };
 
 use self::{settings::Settings, storage::BoardStorage};
 
+mod attack;
 pub mod settings;
 mod storage;
 
@@ -36,6 +41,14 @@ impl Hold {
     }
 }
 
+/// How the active piece was last rotated
+#[derive(Clone, Copy)]
+struct Rotated {
+    spin: Spin,
+    /// Whether the piece needed the last of the kicks of the rotation
+    last_kick: bool,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Garbage {
     amt: u16,
@@ -63,7 +76,7 @@ pub struct Board {
     pub gravity_state: f32,
     settings: Settings,
     /// How many times the piece is able to avoid locking until it is forced to lock immediately.
-    lock_count: i8,
+    lock_count: i32,
     /// How many subframes the active piece has remained in a locking position. Used to caclulate
     /// whether a piece should lock due to the expiration of lock delay.
     lock_timer: u32,
@@ -73,48 +86,131 @@ pub struct Board {
     last_drop: Option<u32>,
     last_drop_needs_update: bool, // TODO way too hacky, redo api to fix this
     hold: Hold,
+    /// Garbage which has been sent to the player but not yet acknowledged, which attacks can
+    /// already cancel. Each is kept along with its sender and the id its sender gave it, so that it
+    /// can be found again once it is acknowledged.
+    received_garbage: VecDeque<(String, u64, Garbage)>,
     acknowledged_garbage: VecDeque<Garbage>, // TODO maybe more performant to combine the vecdeques
     queued_garbage: VecDeque<Garbage>,
+    /// How the active piece was last rotated, if it has not moved in any other way since
+    last_rotation: Option<Rotated>,
+    /// The number of line clears in a row before the latest one, or None if the last piece did not
+    /// clear any lines
+    combo: Option<u16>,
+    /// The number of difficult line clears in a row before the latest one, or None if the last line
+    /// clear was not difficult
+    b2b: Option<u16>,
+    /// The latest subframe whose passive effects have been handled
+    subframe: u32,
+    /// How many lines have been cleared so far, which decides the level in leveled games
+    lines_cleared: u32,
 }
 
 impl Board {
-    /// Creates a new board from a PRNG seed and a board that may be filled with some cells.
+    /// Creates a new board from a PRNG seed and the state of a game, whose matrix may be filled with
+    /// some cells and whose upcoming pieces and hold may already be known. The lines cleared, combo
+    /// and back-to-back are carried over from `stats`, in case the game did not start from scratch.
     ///
     /// The format of the matrix is the same as the format found in ttr and ttrm files -- that is,
-    /// as a two-dimensional matrix.
+    /// as a two-dimensional matrix. Fails if the upcoming pieces or hold name a piece which does
+    /// not exist, or if the pieces are chosen in a way which cannot be simulated.
     pub fn new(
         piece_seed: u64,
-        game_type: GameType,
+        game_type: &GameType,
         settings: Settings,
-        game: &[Vec<Option<&str>>],
-    ) -> (Self, Vec<Action>) {
-        let mut queue = PieceQueue::from_game(game_type, piece_seed);
+        game: &Game,
+        stats: &Stats,
+    ) -> Result<(Self, Vec<Action>), String> {
+        let bag = game
+            .bag
+            .iter()
+            .map(|piece| piece_variant(piece))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut queue = PieceQueue::from_game(game_type, &settings.bag_type, piece_seed, bag)?;
+        let hold = match game.hold.piece {
+            None => Hold::Empty,
+            Some(ref piece) if game.hold.locked => Hold::NotActive(piece_variant(piece)?),
+            Some(ref piece) => Hold::Active(piece_variant(piece)?),
+        };
         let cells = BoardStorage::new_from_rows_unchecked(
-            game.iter()
-                .map(|row| row.iter().map(|elem| Cell::from(*elem)).collect_vec())
+            game.board
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|elem| Cell::from(elem.as_deref()))
+                        .collect_vec()
+                })
                 .rev()
                 .collect_vec(),
         );
 
         let active = queue.pop().into();
+        let lock_count = settings.lock_resets as i32 + 1;
 
-        (
+        Ok((
             Self {
                 matrix: cells,
                 queue,
                 active,
                 gravity_state: 0.0,
                 settings,
-                lock_count: 16,
+                lock_count,
                 lock_timer: 0,
-                hold: Hold::Empty,
+                hold,
                 last_drop: None,
                 last_drop_needs_update: false,
+                received_garbage: VecDeque::new(),
                 acknowledged_garbage: VecDeque::new(),
                 queued_garbage: VecDeque::new(),
+                last_rotation: None,
+                // tetrio counts both from 1, leaving 0 for when there is none
+                combo: stats.combo.checked_sub(1).map(|combo| combo as u16),
+                b2b: stats.btb.checked_sub(1).map(|b2b| b2b as u16),
+                subframe: 0,
+                lines_cleared: stats.lines as u32,
             },
             vec![ActionKind::Reposition { piece: active }.attach_frame(0)],
-        )
+        ))
+    }
+
+    /// Describes the board in the form of the game state of a full event, replacing the matrix,
+    /// upcoming pieces and hold of `game`. The active piece is listed as the next upcoming piece,
+    /// since the game state has no way of describing where it is. Returns the seed to go with the
+    /// upcoming pieces (see [PieceQueue::resume_seed]).
+    pub fn export(&self, game: &mut Game) -> u64 {
+        game.board = self
+            .matrix
+            .rows()
+            .rev()
+            .map(|row| {
+                row.iter()
+                    .map(|&cell| cell_name(cell).map(Cow::Borrowed))
+                    .collect()
+            })
+            .collect();
+        game.bag = iter::once(self.active.variant)
+            .chain(self.queue.upcoming())
+            .map(|variant| Cow::Borrowed(piece_name(variant)))
+            .collect();
+        game.hold = match self.hold {
+            Hold::Empty => event::Hold {
+                locked: false,
+                piece: None,
+            },
+            Hold::Active(variant) | Hold::NotActive(variant) => event::Hold {
+                locked: matches!(self.hold, Hold::NotActive(_)),
+                piece: Some(Cow::Borrowed(piece_name(variant))),
+            },
+        };
+        self.queue.resume_seed(game.bag.len())
+    }
+
+    /// Describes the lines cleared, combo and back-to-back of the game in `stats`, in the form read
+    /// back by [Self::new]
+    pub fn export_stats(&self, stats: &mut Stats) {
+        stats.lines = self.lines_cleared.into();
+        stats.combo = self.combo.map_or(0, |combo| u64::from(combo) + 1);
+        stats.btb = self.b2b.map_or(0, |b2b| u64::from(b2b) + 1);
     }
 
     /// Expends and returns the currently active piece, replacing it with the next piece in the
@@ -125,15 +221,16 @@ impl Board {
 
     /// Shifts the active tetromino by the given amount of cells.
     pub fn shift(&mut self, cells: i8) -> Vec<ActionKind> {
-        let shift_to = self.active.coord.0 + cells as i16;
+        let active = self.active;
+        let shift_to = active.coord.0 + cells as i16;
 
         // ranges are inclusive since the tetromino can at least occupy its current position
-        let shift_through = if self.active.coord.0 < shift_to {
-            itertools::Either::Left(self.active.coord.0..=shift_to)
+        let shift_through = if active.coord.0 < shift_to {
+            itertools::Either::Left(active.coord.0..=shift_to)
         } else {
-            itertools::Either::Right((shift_to..=self.active.coord.0).rev())
+            itertools::Either::Right((shift_to..=active.coord.0).rev())
         }
-        .map(|x| self.active.tap_mut(|piece| piece.coord.0 = x))
+        .map(|x| active.tap_mut(|piece| piece.coord.0 = x))
         .tuple_windows()
         .enumerate();
 
@@ -149,31 +246,61 @@ impl Board {
             // shift (ix != 0) and at the end of the shift (since the loop will break before that
             // happens)
             if ix != 0 && self.will_lock(m1) {
-                self.lock_count -= 1;
+                self.use_lock_reset();
             }
         }
 
         let new_position =
-            new_position.unwrap_or_else(|| self.active.tap_mut(|piece| piece.coord.0 = shift_to));
+            new_position.unwrap_or_else(|| active.tap_mut(|piece| piece.coord.0 = shift_to));
 
-        self.reposition(new_position)
+        self.reposition(new_position, None)
     }
 
-    pub fn acknowledge_garbage(&mut self, garbage: &InteractionData, frame: u32) {
-        match *garbage {
-            InteractionData::InteractionDo { .. } => (), // ignore for now
+    pub fn acknowledge_garbage(&mut self, interaction: &Interaction, frame: u32) {
+        let cid = interaction.cid.as_u64().unwrap_or_default();
+        match interaction.data {
+            InteractionData::InteractionDo {
+                data: ttrm::event::Garbage { amt, column, .. },
+            } => self.received_garbage.push_back((
+                interaction.sender.clone(),
+                cid,
+                Garbage {
+                    amt,
+                    column,
+                    received_frame: frame,
+                },
+            )),
             InteractionData::InteractionConfirm {
                 data: ttrm::event::Garbage { amt, column, .. },
-            } => self.acknowledged_garbage.push_back(Garbage {
-                amt,
-                column,
-                received_frame: frame,
-            }),
+            } => {
+                // whatever was cancelled before the garbage was acknowledged never arrives
+                let received = self
+                    .received_garbage
+                    .iter()
+                    .position(|(sender, id, _)| *sender == interaction.sender && *id == cid);
+                let amt = match received {
+                    Some(ix) => self.received_garbage.remove(ix).unwrap().2.amt,
+                    None => amt,
+                };
+                if amt > 0 {
+                    self.acknowledged_garbage.push_back(Garbage {
+                        amt,
+                        column,
+                        received_frame: frame,
+                    });
+                }
+            }
         }
     }
 
     /// Holds a piece, returning the proper actions depending on whether holding is possible or not.
     pub fn hold(&mut self) -> Vec<ActionKind> {
+        if !matches!(self.hold, Hold::NotActive(_)) {
+            // the piece coming out of hold starts over, just like a new piece
+            self.lock_count = self.settings.lock_resets as i32 + 1;
+            self.lock_timer = 0;
+            self.last_rotation = None;
+        }
         match self.hold {
             Hold::Empty => {
                 self.hold = Hold::NotActive(self.cycle_piece().variant);
@@ -233,11 +360,13 @@ impl Board {
     pub fn passive_effects(&mut self, current_subframe: u32, key_state: &State) -> Vec<Action> {
         (key_state.last_subframe..current_subframe)
             .flat_map(|subframe| {
+                self.subframe = subframe;
                 let settings = &self.settings;
                 let frame = subframe / 10;
 
-                // TODO handle gravity acceleration
-                let gravity_base = settings.gravity.current_gravity(0, subframe);
+                let gravity_base = settings
+                    .gravity
+                    .current_gravity(self.lines_cleared, subframe);
                 self.gravity_state += if key_state.soft_dropping {
                     if settings.sdf > 40 {
                         self.matrix.num_rows().0 as f32 * 10.
@@ -256,7 +385,6 @@ impl Board {
                 }
 
                 // activate garbage if ready
-                // TODO garbage cancellation
                 while let Some(garbage) = self.acknowledged_garbage.get(0) {
                     if garbage.received_frame + settings.garbage_speed - 1 <= frame {
                         self.queued_garbage
@@ -280,12 +408,12 @@ impl Board {
                     new_position.coord.1 -= self.gravity_state.trunc() as i16;
                     new_position.coord.1 = std::cmp::max(new_position.coord.1, locks_at);
                     self.gravity_state = self.gravity_state.fract();
-                    out.extend(self.reposition(new_position))
+                    out.extend(self.reposition(new_position, None))
                 }
 
                 if self.active_will_lock() {
                     self.lock_timer += 1;
-                    if self.lock_timer >= 300 {
+                    if self.lock_timer as u64 >= self.settings.lock_delay * 10 {
                         out.extend(self.drop_active());
                     }
                 }
@@ -340,14 +468,16 @@ impl Board {
             .unwrap() // valid if the mino's current position is guaranteed valid
     }
 
-    /// Repositions the piece to the given position, locking it if necessary.
-    fn reposition(&mut self, to: Mino) -> Vec<ActionKind> {
+    /// Repositions the piece to the given position, locking it if necessary. `rotated` says how
+    /// the piece was rotated to get there, if it was.
+    fn reposition(&mut self, to: Mino, rotated: Option<Rotated>) -> Vec<ActionKind> {
         let mut out = vec![];
 
         if to != self.active {
+            self.last_rotation = rotated;
             self.lock_timer = 0;
             if self.active_will_lock() {
-                self.lock_count -= 1;
+                self.use_lock_reset();
             }
             out.push(ActionKind::Reposition { piece: to });
             self.active = to;
@@ -359,14 +489,30 @@ impl Board {
         out
     }
 
+    /// Uses up one of the times the active piece can avoid locking by moving while resting on the
+    /// stack, unless it can move for as long as it likes.
+    fn use_lock_reset(&mut self) {
+        if !self.settings.infinite_movement {
+            self.lock_count -= 1;
+        }
+    }
+
     /// Drops the active tetromino in the usual way.
     pub fn drop_active(&mut self) -> Vec<ActionKind> {
-        self.lock_count = 16;
+        self.lock_count = self.settings.lock_resets as i32 + 1;
         self.lock_timer = 0;
         let dropping = self.cycle_piece();
         let kind: Cell = dropping.variant.into();
 
-        let dropped = self.will_lock_at(&dropping).position();
+        let landed = self.will_lock_at(&dropping);
+        // a piece dropped from above where it lands was not spun into place
+        let spin = if landed == dropping {
+            self.spin(landed)
+        } else {
+            damage_table::Spin::None
+        };
+        self.last_rotation = None;
+        let dropped = landed.position();
 
         // populate the cells which have been dropped into
         dropped.iter().for_each(|&(x, y)| {
@@ -382,45 +528,168 @@ impl Board {
         self.hold.activate();
         self.last_drop_needs_update = true;
 
-        let new_lines = {
-            let cleared_lines = self.clear_lines();
-            if cleared_lines.is_empty() {
-                self.apply_queued_garbage()
-            } else {
-                cleared_lines
-            }
+        let cleared_lines = self.clear_lines();
+        self.lines_cleared += cleared_lines.len() as u32;
+        let attack = self.attack(dropping.variant, cleared_lines.len(), spin);
+        let cancelled = self.cancel_garbage(attack);
+        let blocked = match self.settings.garbage_blocking {
+            GarbageBlocking::None => false,
+            GarbageBlocking::LimitedBlocking => attack > 0,
+            GarbageBlocking::ComboBlocking | GarbageBlocking::Other(_) => !cleared_lines.is_empty(),
+        };
+        let new_garbage = if blocked {
+            Vec::new()
+        } else {
+            self.apply_queued_garbage()
         };
 
         dropped_cells
-            .chain(new_lines)
+            .chain(iter::once(ActionKind::Lock { spin }))
+            .chain(cleared_lines)
+            .chain((attack > 0).then_some(ActionKind::Attack { amount: attack }))
+            .chain((cancelled > 0).then_some(ActionKind::GarbageCancel { amount: cancelled }))
+            .chain(new_garbage)
             .chain(std::iter::once(ActionKind::Reposition { piece: active }))
             .collect_vec()
     }
 
+    /// Whether the given piece, which is about to lock, was spun into place. Whether a spin counts
+    /// depends on the spin bonuses of the game: T-spins are found with the 3-corner rule, and spins
+    /// of other pieces when the piece cannot move left, right or up.
+    fn spin(&self, mino: Mino) -> damage_table::Spin {
+        let Some(rotated) = self.last_rotation else {
+            return damage_table::Spin::None;
+        };
+        let immobile = || {
+            [(-1, 0), (1, 0), (0, 1)].into_iter().all(|(x, y)| {
+                self.intersects(&mino.tap_mut(|m| m.coord = (m.coord.0 + x, m.coord.1 + y)))
+            })
+        };
+        let is_t = mino.variant == MinoVariant::T;
+
+        match self.settings.spin_bonuses {
+            SpinBonuses::None => damage_table::Spin::None,
+            SpinBonuses::Stupid => damage_table::Spin::Full,
+            SpinBonuses::MiniOnly if is_t => match self.t_spin(mino, rotated) {
+                damage_table::Spin::None => damage_table::Spin::None,
+                _ => damage_table::Spin::Mini,
+            },
+            // the + variants are treated the same as the ones they build on
+            SpinBonuses::All | SpinBonuses::AllPlus if !is_t => {
+                if immobile() {
+                    damage_table::Spin::Full
+                } else {
+                    damage_table::Spin::None
+                }
+            }
+            SpinBonuses::AllMini | SpinBonuses::AllMiniPlus if !is_t => {
+                if immobile() {
+                    damage_table::Spin::Mini
+                } else {
+                    damage_table::Spin::None
+                }
+            }
+            _ if is_t => self.t_spin(mino, rotated),
+            _ => damage_table::Spin::None,
+        }
+    }
+
+    /// Finds T-spins with the 3-corner rule. A T-spin is a mini unless both of the corners the T
+    /// points towards are filled, or the last kick of a (non-180) rotation was used.
+    fn t_spin(&self, mino: Mino, rotated: Rotated) -> damage_table::Spin {
+        let (x, y) = (mino.coord.0 as isize, mino.coord.1 as isize);
+        // clockwise from the top left, so that the corners in front of a T facing in a direction
+        // are at the index of the direction and the one after it
+        let corners = [(-1, 1), (1, 1), (1, -1), (-1, -1)].map(|(dx, dy)| {
+            self.cell(x + dx, y + dy)
+                .map(|cell| !cell.is_empty())
+                .unwrap_or(true)
+        });
+        let front = mino.direction as usize;
+
+        if corners.iter().filter(|&&filled| filled).count() < 3 {
+            damage_table::Spin::None
+        } else if (corners[front] && corners[(front + 1) % 4])
+            || (rotated.last_kick && !matches!(rotated.spin, Spin::Flip))
+        {
+            damage_table::Spin::Full
+        } else {
+            damage_table::Spin::Mini
+        }
+    }
+
+    /// The attack sent by a piece which has just locked, clearing `lines` lines. Keeps count of
+    /// combos and back-to-backs.
+    fn attack(&mut self, piece: MinoVariant, lines: usize, spin: damage_table::Spin) -> u16 {
+        if lines == 0 {
+            self.combo = None;
+            return 0;
+        }
+        let combo = self.combo.map_or(0, |combo| combo + 1);
+        self.combo = Some(combo);
+
+        let mut key = AttackKey {
+            combo,
+            b2b: 0,
+            piece,
+            lines: lines as u8,
+            spin,
+        };
+        self.b2b = attack::is_difficult(&key).then(|| self.b2b.map_or(0, |b2b| b2b + 1));
+        key.b2b = self.b2b.unwrap_or(0);
+
+        let all_clear = self.matrix.rows().flatten().all(Cell::is_empty);
+        attack::attack(&self.settings, &key, all_clear, self.subframe / 10)
+    }
+
+    /// Cancels out incoming garbage with an outgoing attack, starting with the garbage which came in
+    /// first (whether or not it is ready to enter the board). Returns the amount of garbage
+    /// cancelled.
+    fn cancel_garbage(&mut self, attack: u16) -> u16 {
+        let mut remaining = attack;
+        for incoming in [&mut self.queued_garbage, &mut self.acknowledged_garbage] {
+            while remaining > 0 {
+                let Some(garbage) = incoming.front_mut() else {
+                    break;
+                };
+                let cancelled = garbage.amt.min(remaining);
+                garbage.amt -= cancelled;
+                remaining -= cancelled;
+                if garbage.amt == 0 {
+                    incoming.pop_front();
+                }
+            }
+        }
+        // garbage which has not been acknowledged yet is kept around even once it is cancelled
+        // completely, so that its acknowledgement can be recognized
+        for (_, _, garbage) in &mut self.received_garbage {
+            let cancelled = garbage.amt.min(remaining);
+            garbage.amt -= cancelled;
+            remaining -= cancelled;
+        }
+        attack - remaining
+    }
+
     /// Add all garbage to the matrix which has been acknowledged and passed the necessary delay.
     /// Also cuts off any blocks which exceeds the garbage cap, and may cut block in half if it is
     /// necessary.
     fn apply_queued_garbage(&mut self) -> Vec<ActionKind> {
+        let cap = self.settings.garbage_cap_at(self.subframe / 10);
         let mut out = vec![];
         let mut counter = 0;
 
-        while let Some(garbage) = self.queued_garbage.get(0) {
-            // TODO get garbage cap from settings
-            if counter + garbage.amt > self.settings.garbage_cap {
-                let excess = counter + garbage.amt - 8;
-                let applied = garbage.amt - excess;
-
-                out.push(self.apply_garbage((*garbage).tap_mut(|gb| gb.amt = applied)));
-                self.queued_garbage[0].amt = excess;
+        while let Some(&garbage) = self.queued_garbage.front() {
+            if counter >= cap {
                 break;
+            }
+            let applied = garbage.amt.min(cap - counter);
+            if applied == garbage.amt {
+                self.queued_garbage.pop_front();
             } else {
-                let gb = self.queued_garbage.pop_front().unwrap();
-                counter += gb.amt;
-                out.push(self.apply_garbage(gb));
-                if counter == 8 {
-                    break;
-                }
+                self.queued_garbage[0].amt -= applied;
             }
+            counter += applied;
+            out.push(self.apply_garbage(garbage.tap_mut(|gb| gb.amt = applied)));
         }
 
         out
@@ -466,21 +735,23 @@ impl Board {
 
         let true_rotation = rotated.position();
         let kicks = self
-            .active
-            .kick(spin)
+            .settings
+            .kicks
+            .and_then(|table| self.active.kick_in(table, spin))
             .cloned()
-            .map(|i| i.into_iter())
-            .ok_or(vec![].into_iter());
+            .unwrap_or_default();
+        let kick_count = kicks.len();
 
         let accepted_kick = iter::once((0, 0))
-            .chain(Either::from(kicks))
-            .find(|offset| {
+            .chain(kicks)
+            .enumerate()
+            .find(|(_, offset)| {
                 let testing = true_rotation.clone() + *offset;
                 self.test_empty(&testing)
             });
 
         accepted_kick
-            .map(|(x, y)| {
+            .map(|(kick, (x, y))| {
                 self.gravity_state = 0.0;
 
                 let new_position = rotated.tap_mut(
@@ -492,7 +763,8 @@ impl Board {
                         *tet_y += y as i16;
                     },
                 );
-                self.reposition(new_position)
+                let last_kick = kick > 0 && kick == kick_count;
+                self.reposition(new_position, Some(Rotated { spin, last_kick }))
             })
             .unwrap_or(Vec::new())
     }
@@ -531,17 +803,47 @@ impl Board {
     }
 }
 
+/// The piece with the given name in ttr and ttrm files
+fn piece_variant(name: &str) -> Result<MinoVariant, String> {
+    name.parse().map_err(|_| format!("`{name}` is not a piece"))
+}
+
+/// The name of a piece in ttr and ttrm files
+pub(crate) fn piece_name(variant: MinoVariant) -> &'static str {
+    match variant {
+        MinoVariant::L => "l",
+        MinoVariant::J => "j",
+        MinoVariant::T => "t",
+        MinoVariant::Z => "z",
+        MinoVariant::S => "s",
+        MinoVariant::O => "o",
+        MinoVariant::I => "i",
+    }
+}
+
+/// The name of a cell in the matrix of ttr and ttrm files, which is null for empty cells
+pub(crate) fn cell_name(cell: Cell) -> Option<&'static str> {
+    match cell {
+        Cell::Tetromino(variant) => Some(piece_name(variant)),
+        Cell::Garbage => Some("gb"),
+        Cell::Empty => None,
+    }
+}
+
 #[cfg(test)]
 mod test {
 
     use std::collections::VecDeque;
 
-    use itertools::Itertools;
-
-    use super::{settings::Settings, storage::BoardStorage, Board, Hold};
-    use crate::{board::Cell, rng::PieceQueue};
+    use super::{settings::Settings, storage::BoardStorage, Board, Garbage, Hold, Rotated};
+    use crate::{board::Cell, map::Map, reconstruct::State, rng::PieceQueue};
+    use ttrm::event::{GarbageBlocking, Interaction};
 
-    use viewtris::tetromino::{Direction, Mino, MinoVariant, Spin};
+    use viewtris::{
+        action::ActionKind,
+        tables::{damage_table, kick_table},
+        tetromino::{Direction, Mino, MinoVariant, Spin},
+    };
 
     impl Default for Board {
         fn default() -> Self {
@@ -556,8 +858,14 @@ mod test {
                 hold: Hold::Empty,
                 last_drop: None,
                 last_drop_needs_update: false,
+                received_garbage: VecDeque::new(),
                 acknowledged_garbage: VecDeque::new(),
                 queued_garbage: VecDeque::new(),
+                last_rotation: None,
+                combo: None,
+                b2b: None,
+                subframe: 0,
+                lines_cleared: 0,
             }
         }
     }
@@ -565,27 +873,7 @@ mod test {
     /// Takes a map exported from [https://tetrio.team2xh.net/?t=editor] and converts it to
     /// a [BoardStorage]
     fn board_from_string(s: &str) -> BoardStorage<Cell> {
-        use MinoVariant::*;
-        let cells_chunks = s
-            .chars()
-            .filter_map(|ch| match ch {
-                '_' => Some(Cell::Empty),
-                'z' => Some(Cell::Tetromino(Z)),
-                'l' => Some(Cell::Tetromino(L)),
-                'o' => Some(Cell::Tetromino(O)),
-                's' => Some(Cell::Tetromino(S)),
-                'i' => Some(Cell::Tetromino(I)),
-                'j' => Some(Cell::Tetromino(J)),
-                't' => Some(Cell::Tetromino(T)),
-                '#' => Some(Cell::Garbage),
-                _ => None,
-            })
-            .chunks(10);
-        let mut cells = cells_chunks
-            .into_iter()
-            .map(|r| r.collect_vec())
-            .collect_vec();
-
+        let mut cells = s.parse::<Map>().unwrap().board;
         cells.reverse();
 
         BoardStorage::new_from_rows_unchecked(cells)
@@ -635,6 +923,23 @@ mod test {
         assert_eq!(tst_board.active.coord, (4, 1))
     }
 
+    #[test]
+    fn test_kicksets() {
+        let flip = |kicks| {
+            let mut board = resting_t(Settings {
+                kicks,
+                ..Default::default()
+            });
+            board.rotate_active(Spin::Flip);
+            (board.active.direction, board.active.coord)
+        };
+
+        // only SRS+ has kicks for 180 rotations, which lift the flipped T off the floor
+        assert_eq!(flip(Some(&kick_table::SRS_PLUS)), (Direction::Down, (4, 1)));
+        assert_eq!(flip(Some(&kick_table::SRS)), (Direction::Up, (4, 0)));
+        assert_eq!(flip(None), (Direction::Up, (4, 0)));
+    }
+
     #[test]
     fn test_drops() {
         {
@@ -674,4 +979,190 @@ mod test {
             assert_eq!(b.matrix, board_final, "unnatural t skim")
         }
     }
+
+    /// An empty board with a T lying flat on the floor
+    fn resting_t(settings: Settings) -> Board {
+        Board {
+            matrix: board_from_string(&"_".repeat(400)),
+            active: Mino {
+                variant: MinoVariant::T,
+                direction: Direction::Up,
+                coord: (4, 0),
+            },
+            lock_count: settings.lock_resets as i32 + 1,
+            settings,
+            ..Default::default()
+        }
+    }
+
+    fn locks(actions: &[ActionKind]) -> bool {
+        actions
+            .iter()
+            .any(|action| matches!(action, ActionKind::Cell { .. }))
+    }
+
+    #[test]
+    fn test_lock_resets() {
+        let mut board = resting_t(Settings {
+            lock_resets: 2,
+            ..Default::default()
+        });
+        assert!(!locks(&board.shift(1)));
+        assert!(!locks(&board.shift(-1)));
+        assert!(locks(&board.shift(1)), "out of lock resets");
+
+        let mut board = resting_t(Settings {
+            lock_resets: 2,
+            infinite_movement: true,
+            ..Default::default()
+        });
+        for _ in 0..20 {
+            assert!(!locks(&board.shift(1)));
+            assert!(!locks(&board.shift(-1)));
+        }
+    }
+
+    #[test]
+    fn test_lock_delay() {
+        let mut board = resting_t(Settings {
+            lock_delay: 5,
+            ..Default::default()
+        });
+        let actions = board.passive_effects(49, &State::default());
+        assert!(actions.is_empty());
+
+        let state = State {
+            last_subframe: 49,
+            ..Default::default()
+        };
+        let actions = board.passive_effects(50, &state);
+        assert!(locks(
+            &actions.into_iter().map(|a| a.kind).collect::<Vec<_>>()
+        ));
+    }
+
+    #[test]
+    fn test_garbage_cap() {
+        let garbage = |amt, column| Garbage {
+            amt,
+            column,
+            received_frame: 0,
+        };
+        let mut board = resting_t(Settings::default());
+        board.queued_garbage = [garbage(5, 0), garbage(6, 1)].into();
+
+        let entered = board
+            .drop_active()
+            .into_iter()
+            .filter_map(|action| match action {
+                ActionKind::Garbage { column, height } => Some((column, height)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(entered, [(0, 5), (1, 3)]);
+        assert_eq!(board.queued_garbage.len(), 1);
+        assert_eq!(board.queued_garbage[0].amt, 3);
+    }
+
+    #[test]
+    fn test_garbage_cancellation() {
+        let garbage = |amt| Garbage {
+            amt,
+            column: 0,
+            received_frame: 0,
+        };
+        // a t spin double, without the spin, which leaves a cell behind so it isn't an all clear
+        let tsd = |garbage_blocking| Board {
+            matrix: board_from_string(&format!(
+                "{}{}{}{}",
+                "_".repeat(370),
+                "#_________",
+                "####_#####",
+                "###___####"
+            )),
+            settings: Settings {
+                garbage_blocking,
+                ..Default::default()
+            },
+            ..resting_t(Settings::default())
+        };
+        let cancelled_and_entered = |actions: Vec<ActionKind>| {
+            actions
+                .into_iter()
+                .filter_map(|action| match action {
+                    ActionKind::GarbageCancel { amount } => Some((amount, 0)),
+                    ActionKind::Garbage { height, .. } => Some((0, height)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // the oldest garbage is cancelled first, and the rest is blocked by the line clear
+        let mut board = tsd(GarbageBlocking::ComboBlocking);
+        board.queued_garbage = [garbage(1)].into();
+        board.acknowledged_garbage = [garbage(2)].into();
+        assert_eq!(cancelled_and_entered(board.drop_active()), [(1, 0)]);
+        assert!(board.queued_garbage.is_empty());
+        assert_eq!(board.acknowledged_garbage[0].amt, 2);
+
+        // what is left after cancelling enters the board right away
+        let mut board = tsd(GarbageBlocking::None);
+        board.queued_garbage = [garbage(3)].into();
+        assert_eq!(cancelled_and_entered(board.drop_active()), [(1, 0), (0, 2)]);
+        assert!(board.queued_garbage.is_empty());
+
+        // garbage which has been sent but not yet acknowledged can be cancelled as well, and is
+        // acknowledged without what was cancelled
+        let interaction = |kind: &str| {
+            serde_json::from_value::<Interaction>(serde_json::json!({
+                "sender": "opponent",
+                "sent_frame": 0,
+                "cid": 1,
+                "type": kind,
+                "data": { "type": "garbage", "amt": 3, "x": 0, "y": 0, "column": 0 },
+            }))
+            .unwrap()
+        };
+        let mut board = tsd(GarbageBlocking::ComboBlocking);
+        board.acknowledge_garbage(&interaction("interaction"), 0);
+        assert_eq!(cancelled_and_entered(board.drop_active()), [(1, 0)]);
+        board.acknowledge_garbage(&interaction("interaction_confirm"), 10);
+        assert!(board.received_garbage.is_empty());
+        assert_eq!(board.acknowledged_garbage[0].amt, 2);
+    }
+
+    #[test]
+    fn test_t_spin() {
+        let slot = |overhang: &str| Board {
+            matrix: board_from_string(&("_".repeat(370) + overhang + "###___####" + "####_#####")),
+            active: Mino {
+                variant: MinoVariant::T,
+                direction: Direction::Down,
+                coord: (4, 1),
+            },
+            last_rotation: Some(Rotated {
+                spin: Spin::CW,
+                last_kick: false,
+            }),
+            ..Default::default()
+        };
+        let spin = |mut board: Board| {
+            board
+                .drop_active()
+                .into_iter()
+                .find_map(|action| match action {
+                    ActionKind::Lock { spin } => Some(spin),
+                    _ => None,
+                })
+                .unwrap()
+        };
+
+        assert_eq!(spin(slot("___#______")), damage_table::Spin::Full);
+        // only two corners are filled
+        assert_eq!(spin(slot("__________")), damage_table::Spin::None);
+        // the piece was not rotated into place
+        let mut board = slot("___#______");
+        board.last_rotation = None;
+        assert_eq!(spin(board), damage_table::Spin::None);
+    }
 }
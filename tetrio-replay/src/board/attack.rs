@@ -0,0 +1,120 @@
+use ttrm::event::ComboTable;
+use viewtris::tables::damage_table::{
+    AttackKey, Spin, CLASSIC_GUIDELINE_COMBOS, MODERN_GUIDELINE_COMBOS, TETRIO,
+};
+
+use super::settings::Settings;
+
+/// How quickly the back-to-back bonus grows with the length of the chain
+const B2B_BONUS_LOG: f32 = 0.8;
+/// How much of the attack each combo adds with the multiplier combo table
+const COMBO_BONUS: f32 = 0.25;
+/// How quickly the attack of long combos of weak clears grows with the multiplier combo table
+const COMBO_MINIFIER_LOG: f32 = 1.25;
+/// The attack sent by clearing the whole board
+const ALL_CLEAR: f32 = 10.;
+
+/// Whether a line clear continues a back-to-back chain rather than breaking it
+pub fn is_difficult(key: &AttackKey) -> bool {
+    key.lines >= 4 || (key.lines > 0 && key.spin != Spin::None)
+}
+
+/// The attack sent by a line clear with the given settings. In `key`, `combo` is the number of
+/// line clears in a row before this one, and `b2b` is the number of difficult line clears in a row
+/// before this one (if this one is difficult as well). `frame` decides the garbage multiplier.
+pub fn attack(settings: &Settings, key: &AttackKey, all_clear: bool, frame: u32) -> u16 {
+    let mut attack = TETRIO.get(key).unwrap_or(0) as f32;
+
+    if key.lines > 0 && key.b2b > 0 {
+        attack += if settings.b2b_chaining {
+            let chain = (key.b2b as f32 * B2B_BONUS_LOG).ln_1p();
+            let fraction = if key.b2b == 1 {
+                0.
+            } else {
+                (1. + chain % 1.) / 3.
+            };
+            (1. + chain).floor() + fraction
+        } else {
+            1.
+        };
+    }
+
+    if key.combo > 0 {
+        let combo = key.combo as usize;
+        match settings.combo_table {
+            ComboTable::Multiplier => {
+                attack *= 1. + COMBO_BONUS * combo as f32;
+                if combo > 1 {
+                    attack = attack.max((combo as f32 * COMBO_MINIFIER_LOG).ln_1p());
+                }
+            }
+            ComboTable::ClassicGuideline => {
+                attack +=
+                    CLASSIC_GUIDELINE_COMBOS[combo.min(CLASSIC_GUIDELINE_COMBOS.len() - 1)] as f32
+            }
+            ComboTable::ModernGuideline => {
+                attack +=
+                    MODERN_GUIDELINE_COMBOS[combo.min(MODERN_GUIDELINE_COMBOS.len() - 1)] as f32
+            }
+            ComboTable::None | ComboTable::Other(_) => (),
+        }
+    }
+
+    let multiplier = settings.garbage_multiplier_at(frame);
+    let mut attack = (attack * multiplier).floor();
+    if all_clear {
+        attack += (ALL_CLEAR * multiplier).floor();
+    }
+    attack as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use viewtris::tetromino::MinoVariant;
+
+    use super::*;
+
+    fn key(lines: u8, spin: Spin, combo: u16, b2b: u16) -> AttackKey {
+        AttackKey {
+            combo,
+            b2b,
+            piece: MinoVariant::T,
+            lines,
+            spin,
+        }
+    }
+
+    #[test]
+    fn attack_table() {
+        let settings = Settings::default();
+        let attack = |key| attack(&settings, &key, false, 0);
+        assert_eq!(attack(key(1, Spin::None, 0, 0)), 0);
+        assert_eq!(attack(key(4, Spin::None, 0, 0)), 4);
+        assert_eq!(attack(key(2, Spin::Full, 0, 0)), 4);
+        assert_eq!(attack(key(1, Spin::Mini, 0, 0)), 0);
+        // back-to-back quads, then a quad deep into the chain
+        assert_eq!(attack(key(4, Spin::None, 0, 1)), 5);
+        assert_eq!(attack(key(4, Spin::None, 0, 4)), 6);
+        // combos multiply the attack, and weak clears still send something in long combos
+        assert_eq!(attack(key(4, Spin::None, 2, 0)), 6);
+        assert_eq!(attack(key(1, Spin::None, 1, 0)), 0);
+        assert_eq!(attack(key(1, Spin::None, 4, 0)), 1);
+        // an all clear adds to whatever the clear sends by itself
+        assert_eq!(
+            super::attack(&settings, &key(2, Spin::Full, 0, 0), true, 0),
+            14
+        );
+    }
+
+    #[test]
+    fn combo_tables() {
+        let settings = Settings {
+            combo_table: ComboTable::ModernGuideline,
+            b2b_chaining: false,
+            ..Settings::default()
+        };
+        let attack = |key| attack(&settings, &key, false, 0);
+        assert_eq!(attack(key(1, Spin::None, 5, 0)), 2);
+        assert_eq!(attack(key(2, Spin::Full, 20, 3)), 4 + 1 + 4);
+    }
+}
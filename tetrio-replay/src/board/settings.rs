@@ -1,15 +1,47 @@
-use ttrm::event::GameOptions;
+use serde_json::Number;
+use ttrm::event::{BagType, ComboTable, GameOptions, GarbageBlocking, KickSet, SpinBonuses};
+use viewtris::tables::kick_table::{self, KickTable};
 
 pub struct Settings {
     pub gravity: GravitySettings,
     /// Measured in frames, not subframes
     pub garbage_speed: u32,
+    /// The most garbage which can enter the board at once
     pub garbage_cap: u16,
+    /// How much the garbage cap grows each second once the garbage margin has passed
+    pub garbage_cap_increase: f32,
+    /// How far the garbage cap can grow
+    pub garbage_cap_max: u16,
+    /// The number of frames before the garbage cap and multiplier start to grow
+    pub garbage_margin: u32,
+    /// How much attack is multiplied by
+    pub garbage_multiplier: f32,
+    /// How much the garbage multiplier grows each second once the garbage margin has passed
+    pub garbage_increase: f32,
+    /// How combos add to attack
+    pub combo_table: ComboTable,
+    /// Whether the back-to-back bonus grows with the length of the back-to-back chain
+    pub b2b_chaining: bool,
     pub das: u32,
     pub arr: u32,
     pub sdf: u32,
     pub dcd: u32,
+    /// Whether clearing lines keeps incoming garbage from entering the board. With limited blocking,
+    /// only line clears which send an attack keep garbage out.
+    pub garbage_blocking: GarbageBlocking,
+    /// Which pieces are rewarded for being spun into place
+    pub spin_bonuses: SpinBonuses,
+    /// Measured in frames, not subframes
     pub lock_delay: u64,
+    /// How many times the active piece can move or rotate while resting on the stack before it
+    /// locks immediately
+    pub lock_resets: u32,
+    /// Whether moving or rotating the active piece resets lock delay without any limit
+    pub infinite_movement: bool,
+    /// The kicks tried when rotating the active piece, or None if pieces only rotate in place
+    pub kicks: Option<&'static KickTable>,
+    /// How the pieces of the queue are chosen
+    pub bag_type: BagType,
 }
 
 impl Default for Settings {
@@ -18,22 +50,97 @@ impl Default for Settings {
             gravity: GravitySettings::default(),
             garbage_speed: 20,
             garbage_cap: 8,
+            garbage_cap_increase: 0.,
+            garbage_cap_max: 40,
+            garbage_margin: 0,
+            garbage_multiplier: 1.,
+            garbage_increase: 0.,
+            combo_table: ComboTable::Multiplier,
+            b2b_chaining: true,
             das: 100,
             arr: 20,
             sdf: 60,
             dcd: 10,
+            garbage_blocking: GarbageBlocking::ComboBlocking,
+            spin_bonuses: SpinBonuses::TSpins,
             lock_delay: 30,
+            lock_resets: 15,
+            infinite_movement: false,
+            kicks: Some(&kick_table::SRS_PLUS),
+            bag_type: BagType::SevenBag,
         }
     }
 }
 
-impl<'a, 'b> From<&'a GameOptions<'b>> for Settings {
-    fn from(options: &'a GameOptions<'b>) -> Self {
+impl<'a> TryFrom<&'a GameOptions> for Settings {
+    type Error = String;
+
+    /// Reads the settings of a game from its options. Fails if the game was played with a kickset
+    /// which cannot be simulated, so that a replay is not silently reconstructed with the wrong
+    /// kicks.
+    fn try_from(options: &'a GameOptions) -> Result<Self, String> {
+        let kicks = match options.kickset {
+            KickSet::SrsPlus => Some(&*kick_table::SRS_PLUS),
+            KickSet::Srs => Some(&*kick_table::SRS),
+            KickSet::None => None,
+            ref kickset @ (KickSet::SrsX
+            | KickSet::TetraX
+            | KickSet::Nrs
+            | KickSet::Ars
+            | KickSet::Asc
+            | KickSet::Other(_)) => {
+                return Err(format!("the kickset `{kickset}` is not supported"))
+            }
+        };
+
         let mut settings = Self {
             gravity: options.into(),
+            garbage_blocking: options
+                .garbage_blocking
+                .clone()
+                .unwrap_or(GarbageBlocking::ComboBlocking),
+            spin_bonuses: options.spin_bonuses.clone().unwrap_or(SpinBonuses::TSpins),
             lock_delay: options.lock_time.unwrap_or(30),
+            lock_resets: options
+                .lock_resets
+                .as_ref()
+                .and_then(Number::as_u64)
+                .unwrap_or(15) as u32,
+            infinite_movement: options.infinite_movement.unwrap_or(false),
             garbage_speed: options.garbage_speed,
             garbage_cap: options.garbage_cap,
+            garbage_cap_increase: options
+                .garbage_cap_increase
+                .as_ref()
+                .and_then(Number::as_f64)
+                .unwrap_or(0.) as f32,
+            garbage_cap_max: options
+                .garbage_cap_max
+                .as_ref()
+                .and_then(Number::as_u64)
+                .map_or(40, |max| max as u16),
+            garbage_margin: options
+                .garbage_margin
+                .as_ref()
+                .and_then(Number::as_u64)
+                .unwrap_or(0) as u32,
+            garbage_multiplier: options
+                .garbage_multiplier
+                .as_ref()
+                .and_then(Number::as_f64)
+                .unwrap_or(1.) as f32,
+            garbage_increase: options
+                .garbage_increase
+                .as_ref()
+                .and_then(Number::as_f64)
+                .unwrap_or(0.) as f32,
+            combo_table: options
+                .combo_table
+                .clone()
+                .unwrap_or(ComboTable::Multiplier),
+            b2b_chaining: options.b2b_chaining.unwrap_or(true),
+            kicks,
+            bag_type: options.bag_type.clone().unwrap_or(BagType::SevenBag),
             ..Default::default()
         };
 
@@ -44,66 +151,240 @@ impl<'a, 'b> From<&'a GameOptions<'b>> for Settings {
             settings.dcd = (handling.dcd * 10.).round() as u32;
         }
 
-        settings
+        Ok(settings)
+    }
+}
+
+impl Settings {
+    /// The most garbage which can enter the board at once on `frame`. Once the garbage margin has
+    /// passed, the cap grows until it reaches its maximum.
+    pub fn garbage_cap_at(&self, frame: u32) -> u16 {
+        let frames_increased = frame.saturating_sub(self.garbage_margin);
+        let cap =
+            self.garbage_cap as f32 + self.garbage_cap_increase * frames_increased as f32 / 60.;
+        (cap as u16).min(self.garbage_cap_max.max(self.garbage_cap))
+    }
+
+    /// How much attack is multiplied by on `frame`, which grows once the garbage margin has passed
+    pub fn garbage_multiplier_at(&self, frame: u32) -> f32 {
+        let frames_increased = frame.saturating_sub(self.garbage_margin);
+        self.garbage_multiplier + self.garbage_increase * frames_increased as f32 / 60.
     }
 }
 
 pub enum GravitySettings {
+    /// Gravity which speeds up as the player levels up by clearing lines
     Leveled {
+        /// Scales the number of lines needed to level up, which grows with each level
         level_speed: f32,
+        /// The number of lines needed to level up, if it is the same for every level
+        level_static: Option<u32>,
+        /// The seconds it takes to fall a row are `(base_gravity - (level - 1) * gravity_speed)`
+        /// to the power of `level - 1`
         base_gravity: f32,
+        gravity_speed: f32,
+        starting_level: u32,
+        /// Whether gravity becomes 20G once level 20 is reached
+        master_levels: bool,
     },
+    /// Gravity which speeds up over time
     Continuous {
         gravity: f32,
+        /// How much gravity increases each second once the margin has passed
         gravity_increase: Option<f32>,
+        /// The number of frames before gravity starts to increase
+        gravity_margin: u32,
     },
 }
 
+/// The greatest possible gravity, which drops a piece the full height of a standard board each frame
+const MAX_GRAVITY: f32 = 20.;
+
 impl Default for GravitySettings {
     fn default() -> Self {
         Self::Continuous {
             gravity: 0.02,
             gravity_increase: Some(0.0035),
+            gravity_margin: 7200,
         }
     }
 }
 
-impl<'a, 'b> From<&'a GameOptions<'b>> for GravitySettings {
-    fn from(options: &'a GameOptions<'b>) -> Self {
+impl<'a> From<&'a GameOptions> for GravitySettings {
+    fn from(options: &'a GameOptions) -> Self {
         if options.levels.unwrap_or(false) {
             Self::Leveled {
-                level_speed: options.level_speed.unwrap(),
-                base_gravity: options.gravity_base.unwrap(),
+                level_speed: options.level_speed.unwrap_or(1.),
+                level_static: options
+                    .level_static
+                    .unwrap_or(false)
+                    .then(|| options.level_static_speed.as_ref()?.as_f64())
+                    .flatten()
+                    .map(|lines| lines as u32),
+                base_gravity: options.gravity_base.unwrap_or(0.8),
+                gravity_speed: options
+                    .gravity_speed
+                    .as_ref()
+                    .and_then(Number::as_f64)
+                    .unwrap_or(0.007) as f32,
+                starting_level: options
+                    .starting_level
+                    .as_ref()
+                    .and_then(Number::as_u64)
+                    .unwrap_or(1) as u32,
+                master_levels: options.master_levels.unwrap_or(false),
             }
         } else {
             Self::Continuous {
                 gravity: options.gravity.unwrap(),
                 gravity_increase: options.gravity_increase,
+                gravity_margin: options
+                    .gravity_margin
+                    .as_ref()
+                    .and_then(Number::as_u64)
+                    .unwrap_or(0) as u32,
             }
         }
     }
 }
 
 impl GravitySettings {
+    /// The gravity in rows per frame after `lines_cleared` lines have been cleared, at `subframe`
     pub fn current_gravity(&self, lines_cleared: u32, subframe: u32) -> f32 {
-        match self {
+        match *self {
             GravitySettings::Leveled {
-                level_speed,
                 base_gravity,
+                gravity_speed,
+                master_levels,
+                ..
             } => {
-                let levels_up = (1..)
-                    .scan(0, |st, i| {
-                        *st += (5. * level_speed * (i as f32)).ceil() as u32;
-                        Some((1, *st))
-                    })
-                    .find_map(|(level, min_lines)| (lines_cleared >= min_lines).then_some(level))
-                    .unwrap_or(0); // an answer exists unless the player hasn't leveled up yet
-                todo!()
+                let level = self.level(lines_cleared);
+                let base = base_gravity - (level - 1) as f32 * gravity_speed;
+                if (master_levels && level >= 20) || base <= 0. {
+                    MAX_GRAVITY
+                } else {
+                    let seconds_per_row = base.powi(level as i32 - 1);
+                    f32::min(1. / (60. * seconds_per_row), MAX_GRAVITY)
+                }
             }
             GravitySettings::Continuous {
                 gravity,
                 gravity_increase,
-            } => f32::max(*gravity, 0.05),
+                gravity_margin,
+            } => {
+                let frames_increased = (subframe / 10).saturating_sub(gravity_margin);
+                let increase = gravity_increase.unwrap_or(0.) * frames_increased as f32 / 60.;
+                (gravity + increase).clamp(0.05, MAX_GRAVITY)
+            }
+        }
+    }
+
+    /// The level reached after `lines_cleared` lines have been cleared, which is always 1 for
+    /// continuous gravity. Leaving a level takes `level_static` lines, or else
+    /// `ceil(5 * level_speed * level)` lines.
+    pub fn level(&self, lines_cleared: u32) -> u32 {
+        match *self {
+            GravitySettings::Leveled {
+                level_speed,
+                level_static,
+                starting_level,
+                ..
+            } => {
+                let mut level = starting_level.max(1);
+                let mut lines = lines_cleared;
+                loop {
+                    let needed = level_static
+                        .unwrap_or_else(|| (5. * level_speed * level as f32).ceil() as u32)
+                        .max(1);
+                    if lines < needed {
+                        return level;
+                    }
+                    lines -= needed;
+                    level += 1;
+                }
+            }
+            GravitySettings::Continuous { .. } => 1,
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{GravitySettings, Settings};
+
+    #[test]
+    fn garbage_cap() {
+        let settings = Settings {
+            garbage_cap: 8,
+            garbage_cap_increase: 0.5,
+            garbage_cap_max: 12,
+            garbage_margin: 600,
+            ..Default::default()
+        };
+        assert_eq!(settings.garbage_cap_at(0), 8);
+        assert_eq!(settings.garbage_cap_at(600), 8);
+        assert_eq!(settings.garbage_cap_at(720), 9);
+        assert_eq!(settings.garbage_cap_at(1079), 11);
+        assert_eq!(settings.garbage_cap_at(1080), 12);
+        assert_eq!(settings.garbage_cap_at(100_000), 12);
+    }
+
+    /// The gravity of blitz, with some settings changed
+    fn leveled(
+        level_static: Option<u32>,
+        starting_level: u32,
+        master_levels: bool,
+    ) -> GravitySettings {
+        GravitySettings::Leveled {
+            level_speed: 0.42,
+            level_static,
+            base_gravity: 0.65,
+            gravity_speed: 0.007,
+            starting_level,
+            master_levels,
+        }
+    }
+
+    #[test]
+    fn levels() {
+        let blitz = leveled(None, 1, false);
+        // levels 1 to 8 take 3, 5, 7, ..., 17 lines
+        assert_eq!(blitz.level(0), 1);
+        assert_eq!(blitz.level(2), 1);
+        assert_eq!(blitz.level(3), 2);
+        assert_eq!(blitz.level(79), 8);
+        assert_eq!(blitz.level(80), 9);
+        // as found at the end of the blitz sample
+        assert_eq!(blitz.level(85), 9);
+
+        let level_static = leveled(Some(10), 3, false);
+        assert_eq!(level_static.level(9), 3);
+        assert_eq!(level_static.level(25), 5);
+    }
+
+    #[test]
+    fn gravity_increase() {
+        let league = GravitySettings::default();
+        // as found at the end of a round in the league sample
+        assert!((league.current_gravity(0, 143190) - 0.435275).abs() < 1e-5);
+
+        let constant = GravitySettings::Continuous {
+            gravity: 0.2,
+            gravity_increase: None,
+            gravity_margin: 0,
+        };
+        assert_eq!(constant.current_gravity(0, 143190), 0.2);
+    }
+
+    #[test]
+    fn leveled_gravity() {
+        let blitz = leveled(None, 1, false);
+        // as found in the game states of the blitz sample
+        assert!((blitz.current_gravity(0, 0) - 1. / 60.).abs() < 1e-6);
+        assert!((blitz.current_gravity(85, 0) - 1.0753682).abs() < 1e-4);
+
+        let master = leveled(Some(1), 1, true);
+        assert!(master.current_gravity(9, 0) < 20.);
+        assert_eq!(master.current_gravity(19, 0), 20.);
+    }
+}
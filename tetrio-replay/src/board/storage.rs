@@ -30,6 +30,11 @@ impl<T> BoardStorage<T> {
         }
     }
 
+    /// The rows of the grid, from the bottom up
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[T]> {
+        self.storage.iter().map(Vec::as_slice)
+    }
+
     #[cfg(test)]
     pub fn new_empty() -> Self {
         Self {
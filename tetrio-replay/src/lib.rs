@@ -1,7 +1,12 @@
 mod board;
+mod map;
 mod reconstruct;
 mod rng;
 
-pub use reconstruct::reconstruct;
+pub use map::{Map, MapError};
+pub use reconstruct::{
+    extract, reconstruct, reconstruct_events, reconstruct_ttrm_stream, state_at, ReplayActions,
+    ReplayStream,
+};
 pub use ttrm;
 pub use viewtris; // re-export of ttrm crate
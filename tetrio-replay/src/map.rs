@@ -0,0 +1,192 @@
+//! The map strings used by TETR.IO custom rooms and the map editor at
+//! [https://tetrio.team2xh.net/?t=editor]. A map string is made up of up to three sections
+//! separated by `?`: the cells of the board, read row by row from the top left, then the upcoming
+//! pieces, then the held piece. Cells are written as the lowercase letter of their piece, `#` for
+//! garbage and `_` for empty cells; pieces are written as their letter.
+
+use std::{borrow::Cow, fmt::Display, str::FromStr};
+
+use itertools::Itertools;
+use ttrm::event::{self, Game};
+use viewtris::tetromino::{Cell, MinoVariant};
+
+use crate::board::{cell_name, piece_name};
+
+/// Why a map string could not be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapError {
+    /// The number of cells on the board is not a multiple of the width of the board
+    UnevenBoard { cells: usize, width: usize },
+    /// A cell on the board is written with a character which does not stand for any cell
+    UnknownCell(char),
+    /// A piece in the queue or hold is written with a character which does not stand for any piece
+    UnknownPiece(char),
+    /// More than one piece is held
+    MultipleHold,
+    /// The string has more than three sections
+    TooManySections,
+}
+
+impl Display for MapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapError::UnevenBoard { cells, width } => write!(
+                f,
+                "the board has {cells} cells, which do not make up rows of {width} cells"
+            ),
+            MapError::UnknownCell(ch) => write!(f, "unknown cell `{ch}`"),
+            MapError::UnknownPiece(ch) => write!(f, "unknown piece `{ch}`"),
+            MapError::MultipleHold => write!(f, "only one piece can be held"),
+            MapError::TooManySections => {
+                write!(f, "a map has at most three sections separated by `?`")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+/// A starting board, along with the pieces to come and the held piece
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Map {
+    /// The rows of the board from the top down, as in the matrix of ttr and ttrm files
+    pub board: Vec<Vec<Cell>>,
+    pub queue: Vec<MinoVariant>,
+    pub hold: Option<MinoVariant>,
+}
+
+impl Map {
+    /// Reads a map string whose board is `width` cells wide
+    pub fn parse(s: &str, width: usize) -> Result<Self, MapError> {
+        let mut sections = s.trim().split('?');
+        let board = sections.next().unwrap_or_default();
+        let queue = sections.next().unwrap_or_default();
+        let hold = sections.next().unwrap_or_default();
+        if sections.next().is_some() {
+            return Err(MapError::TooManySections);
+        }
+
+        let cells = board
+            .chars()
+            .map(|ch| map_cell(ch).ok_or(MapError::UnknownCell(ch)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if width == 0 || cells.len() % width != 0 {
+            return Err(MapError::UnevenBoard {
+                cells: cells.len(),
+                width,
+            });
+        }
+
+        let mut hold = pieces(hold)?;
+        if hold.len() > 1 {
+            return Err(MapError::MultipleHold);
+        }
+
+        Ok(Self {
+            board: cells.chunks(width).map(<[_]>::to_vec).collect(),
+            queue: pieces(queue)?,
+            hold: hold.pop(),
+        })
+    }
+
+    /// Reads the board, upcoming pieces and hold of the game state of a full event
+    pub fn from_game(game: &Game) -> Self {
+        Self {
+            board: game
+                .board
+                .iter()
+                .map(|row| row.iter().map(|cell| Cell::from(cell.as_deref())).collect())
+                .collect(),
+            queue: game
+                .bag
+                .iter()
+                .filter_map(|piece| piece.parse().ok())
+                .collect(),
+            hold: game
+                .hold
+                .piece
+                .as_ref()
+                .and_then(|piece| piece.parse().ok()),
+        }
+    }
+
+    /// Replaces the board, upcoming pieces and hold of the game state of a full event. A board
+    /// with fewer rows than the game already has is padded with empty rows at the top.
+    pub fn apply(&self, game: &mut Game) {
+        let width = self.width();
+        let padding = game.board.len().saturating_sub(self.board.len());
+        game.board = std::iter::repeat_with(|| vec![None; width])
+            .take(padding)
+            .chain(self.board.iter().map(|row| {
+                row.iter()
+                    .map(|&cell| cell_name(cell).map(Cow::Borrowed))
+                    .collect()
+            }))
+            .collect();
+        game.bag = self
+            .queue
+            .iter()
+            .map(|&piece| Cow::Borrowed(piece_name(piece)))
+            .collect();
+        game.hold = event::Hold {
+            locked: false,
+            piece: self.hold.map(|piece| Cow::Borrowed(piece_name(piece))),
+        };
+    }
+
+    /// The number of columns of the board
+    pub fn width(&self) -> usize {
+        self.board.first().map_or(0, Vec::len)
+    }
+}
+
+impl Display for Map {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let board = self.board.iter().flatten().map(|&cell| match cell {
+            Cell::Tetromino(piece) => piece_char(piece),
+            Cell::Garbage => '#',
+            Cell::Empty => '_',
+        });
+        write!(f, "{}", board.format(""))?;
+        if !self.queue.is_empty() || self.hold.is_some() {
+            let queue = self.queue.iter().map(|&piece| piece_char(piece));
+            write!(f, "?{}", queue.format(""))?;
+        }
+        if let Some(piece) = self.hold {
+            write!(f, "?{}", piece_char(piece))?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads a map string with a board of the standard width of 10 columns
+impl FromStr for Map {
+    type Err = MapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s, 10)
+    }
+}
+
+fn pieces(section: &str) -> Result<Vec<MinoVariant>, MapError> {
+    section
+        .chars()
+        .map(|ch| map_piece(ch).ok_or(MapError::UnknownPiece(ch)))
+        .collect()
+}
+
+fn map_cell(ch: char) -> Option<Cell> {
+    match ch {
+        '_' => Some(Cell::Empty),
+        '#' => Some(Cell::Garbage),
+        ch => map_piece(ch).map(Cell::Tetromino),
+    }
+}
+
+fn map_piece(ch: char) -> Option<MinoVariant> {
+    ch.to_string().parse().ok()
+}
+
+fn piece_char(piece: MinoVariant) -> char {
+    piece_name(piece).chars().next().unwrap()
+}
@@ -1,6 +1,14 @@
+use std::{
+    borrow::Borrow,
+    io::Read,
+    iter::{self, Peekable},
+    ops::Range,
+};
+
 use ttrm::{
-    event::{Event, EventData, EventFull, Game, Key, KeyEvent},
-    GameType,
+    event::{Event, EventData, EventFull, Key, KeyEvent},
+    stream::{EventStream, StreamedEvent},
+    GameType, Replay,
 };
 use viewtris::{action::Action, tetromino::Spin};
 
@@ -108,25 +116,27 @@ impl State {
 
 impl<'a, It> Controller<It>
 where
-    It: Iterator<Item = &'a Event<'a>>,
+    It: Iterator,
+    It::Item: Borrow<Event<'a>>,
 {
     /// Creates a controller from a series of tetrio events
-    fn read_game(mut game: It, game_type: GameType) -> Result<Self, &'static str> {
+    fn read_game(mut game: It, game_type: &GameType) -> Result<Self, String> {
         loop {
             let next = game.next();
 
-            match next {
+            match next.as_ref().map(Borrow::borrow) {
                 Some(Event {
                     data: EventData::Full { data },
                     ..
                 }) => {
                     let EventFull {
                         ref options,
-                        game: Game { ref board, .. },
+                        game: ref state,
+                        ref stats,
                         ..
                     } = **data;
                     let (board, stream) =
-                        Board::new(options.seed, game_type, options.into(), board);
+                        Board::new(options.seed, game_type, options.try_into()?, state, stats)?;
                     break Some(Self {
                         events: game,
                         board,
@@ -138,13 +148,13 @@ where
                 _ => continue, // keep searching for full data
             }
         }
-        .ok_or("could not find full data to extract initial game state from")
+        .ok_or_else(|| "could not find full data to extract initial game state from".to_owned())
     }
 
-    fn stream(mut self) -> Result<Vec<Action>, String> {
-        let mut initial_frame = 0;
-        self.events.for_each(|event| {
-            initial_frame = event.frame;
+    /// Plays out every remaining event
+    fn play(&mut self) {
+        for event in self.events.by_ref() {
+            let event = event.borrow();
 
             match event.data {
                 EventData::Start {} => (),
@@ -171,15 +181,153 @@ where
                     event: ref ingame_event,
                 } => self
                     .board
-                    .acknowledge_garbage(&ingame_event.data.data, event.frame),
-                EventData::End {} => (),
+                    .acknowledge_garbage(&ingame_event.data, event.frame),
+                EventData::End { .. } => (),
             }
-        });
+        }
+    }
 
+    fn stream(mut self) -> Result<Vec<Action>, String> {
+        self.play();
         Ok(self.stream)
     }
 }
 
-pub fn reconstruct(game_type: GameType, event_stream: &[Event]) -> Result<Vec<Action>, String> {
-    Controller::read_game(event_stream.iter(), game_type)?.stream()
+pub fn reconstruct(game_type: &GameType, event_stream: &[Event]) -> Result<Vec<Action>, String> {
+    reconstruct_events(game_type, event_stream)
+}
+
+/// Reconstructs a game from events which may be produced one at a time, such as the events of a
+/// [ttrm::stream::EventStream]
+pub fn reconstruct_events<'a>(
+    game_type: &GameType,
+    events: impl IntoIterator<Item = impl Borrow<Event<'a>>>,
+) -> Result<Vec<Action>, String> {
+    Controller::read_game(events.into_iter(), game_type)?.stream()
+}
+
+/// The actions of one replay of a ttrm file (see [reconstruct_ttrm_stream])
+#[derive(Debug)]
+pub struct ReplayActions {
+    /// The index of the round (see [ttrm::Ttrm::data]) the replay was found in
+    pub round: usize,
+    /// The index of the replay within its round (see [ttrm::ReplaySet::replays])
+    pub player: usize,
+    pub actions: Vec<Action>,
+}
+
+/// Reconstructs the replays of a ttrm file one after another, as they are read (see
+/// [reconstruct_ttrm_stream])
+pub struct ReplayStream<R: Read> {
+    events: Peekable<EventStream<R>>,
+    game_type: GameType,
+}
+
+impl<R: Read> Iterator for ReplayStream<R> {
+    type Item = Result<ReplayActions, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (round, player) = match self.events.peek()? {
+            Ok(streamed) => (streamed.round, streamed.player),
+            Err(_) => {
+                return self
+                    .events
+                    .next()
+                    .map(|error| Err(error.unwrap_err().to_string()))
+            }
+        };
+        let in_replay = |event: &Result<StreamedEvent, _>| matches!(event, Ok(streamed) if (streamed.round, streamed.player) == (round, player));
+
+        // an error in an event ends the replay it was found in
+        let mut error = None;
+        let events = iter::from_fn(|| {
+            match self
+                .events
+                .next_if(|event| event.is_err() || in_replay(event))?
+            {
+                Ok(streamed) => Some(streamed.event),
+                Err(e) => {
+                    error = Some(e.to_string());
+                    None
+                }
+            }
+        });
+        let actions = reconstruct_events(&self.game_type, events);
+        // skip whatever is left of the replay, if it could not be played back
+        while self.events.next_if(in_replay).is_some() {}
+
+        Some(match error {
+            Some(error) => Err(error),
+            None => actions.map(|actions| ReplayActions {
+                round,
+                player,
+                actions,
+            }),
+        })
+    }
+}
+
+/// Reconstructs every replay in a ttrm file, reading the file as it goes instead of parsing it
+/// whole up front. Replays are given round by round, then player by player, and only the events of
+/// the replay being reconstructed are held at any time.
+pub fn reconstruct_ttrm_stream<R: Read>(reader: R) -> Result<ReplayStream<R>, String> {
+    let events = ttrm::stream::ttrm_events(reader).map_err(|e| e.to_string())?;
+    Ok(ReplayStream {
+        game_type: events.game_type().clone(),
+        events: events.peekable(),
+    })
+}
+
+/// Simulates a game up to the start of `frame`, describing its state at that point as a full event.
+/// This can take the place of the original full event in a replay of the rest of the game (see
+/// [extract]).
+///
+/// The original full event is copied, with the matrix, upcoming pieces, hold and seed replaced, and
+/// with the lines cleared, combo and back-to-back of its stats replaced. Only the state needed to
+/// carry on the game is kept: the active piece goes back to the top of the board, and garbage which
+/// has not yet reached the board is lost.
+///
+/// Nothing records how long the game has gone on for, so whatever grows over time (continuous
+/// gravity, the garbage cap and the garbage multiplier) starts over from `frame` when the game is
+/// carried on from this state.
+pub fn state_at<'a>(
+    game_type: &GameType,
+    events: &[Event<'a>],
+    frame: u32,
+) -> Result<EventFull<'a>, String> {
+    let (full_index, full) = events
+        .iter()
+        .enumerate()
+        .find_map(|(index, event)| match event.data {
+            EventData::Full { ref data } => Some((index, data)),
+            _ => None,
+        })
+        .ok_or_else(|| "could not find full data to extract initial game state from".to_owned())?;
+    let cut = events
+        .iter()
+        .position(|event| event.frame >= frame)
+        .unwrap_or(events.len())
+        .max(full_index + 1);
+
+    let mut controller = Controller::read_game(events[..cut].iter(), game_type)?;
+    controller.play();
+    controller
+        .board
+        .passive_effects(frame * 10, &controller.state);
+
+    let mut state = (**full).clone();
+    state.options.seed = controller.board.export(&mut state.game);
+    controller.board.export_stats(&mut state.stats);
+    Ok(state)
+}
+
+/// Extracts the part of a replay played on `frames` as a replay of its own (see
+/// [ttrm::Replay::extract]), simulating the game to find its state at the start of the range
+pub fn extract<'a>(
+    game_type: &GameType,
+    replay: &Replay<'a>,
+    frames: Range<u32>,
+) -> Result<Replay<'a>, String> {
+    let full = state_at(game_type, &replay.events, frames.start)?;
+    Ok(replay.extract(frames, full))
 }
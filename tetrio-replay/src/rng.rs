@@ -1,8 +1,15 @@
 use std::collections::VecDeque;
 
-use ttrm::GameType;
+use ttrm::{event::BagType, GameType};
 use viewtris::tetromino::MinoVariant;
 
+/// The modulus of the RNG
+const MODULUS: u64 = 2147483647;
+/// The multiplier of the RNG
+const MULTIPLIER: u64 = 16807;
+/// The inverse of [MULTIPLIER] modulo [MODULUS], which steps the RNG backwards
+const INVERSE_MULTIPLIER: u64 = 1407677000;
+
 /// The RNG used by tetrio to generate new pieces.
 pub struct Rng {
     state: u64,
@@ -11,15 +18,20 @@ pub struct Rng {
 impl Rng {
     fn seeded(seed: u64) -> Self {
         Rng {
-            state: seed % 2147483647,
+            state: seed % MODULUS,
         }
     }
 
     fn next(&mut self) -> u64 {
-        self.state = 16807 * self.state % 2147483647;
+        self.state = MULTIPLIER * self.state % MODULUS;
         self.state
     }
 
+    /// The state the RNG was in `steps` numbers ago
+    fn rewound(&self, steps: usize) -> u64 {
+        (0..steps).fold(self.state, |state, _| INVERSE_MULTIPLIER * state % MODULUS)
+    }
+
     fn next_float(&mut self) -> f64 {
         (self.next() - 1) as f64 / 2147483646.0
     }
@@ -68,12 +80,53 @@ impl PieceQueue {
         Self { rng, base, window }
     }
 
-    pub fn from_game(game: GameType, seed: u64) -> Self {
+    /// Creates the piece queue of a game from its seed and the upcoming pieces listed in its full
+    /// event.
+    ///
+    /// The listed pieces are dealt first. Since they were dealt by the RNG, the bags they came from
+    /// are skipped before the RNG deals any more pieces: at the start of a game, the listed pieces
+    /// are exactly the first bag.
+    ///
+    /// Fails if the pieces are chosen in a way which cannot be simulated.
+    pub fn from_game(
+        game: &GameType,
+        bag_type: &BagType,
+        seed: u64,
+        upcoming: impl IntoIterator<Item = MinoVariant>,
+    ) -> Result<Self, String> {
+        let mut queue = Self::from_seed(game, bag_type, seed)?;
+        queue.window = upcoming.into_iter().collect();
+        for _ in 0..queue.bags_dealt(queue.window.len()) {
+            queue.rng.shuffle_boxed_slice(queue.base.clone());
+        }
+        Ok(queue)
+    }
+
+    fn from_seed(game: &GameType, bag_type: &BagType, seed: u64) -> Result<Self, String> {
+        match bag_type {
+            BagType::SevenBag | BagType::LegacySevenBag => Ok(Self::seven_bag(game, seed)),
+            BagType::FourteenBag => Ok(Self::fourteen_bag(seed)),
+            bag @ (BagType::Classic
+            | BagType::Pairs
+            | BagType::TotalMayhem
+            | BagType::SevenPlusOne
+            | BagType::SevenPlusTwo
+            | BagType::SevenPlusX
+            | BagType::Other(_)) => Err(format!("the bag type `{bag}` is not supported")),
+        }
+    }
+
+    fn seven_bag(game: &GameType, seed: u64) -> Self {
         match game {
             // GameType::FortyLine => Self::fortyline(seed),
             GameType::FortyLine => Self::standard(seed),
             GameType::League | GameType::Custom | GameType::Blitz => Self::standard(seed),
-            _ => unimplemented!("It is not yet known what queue satisfies this game type"),
+            // every other mode seen so far uses the standard queue, so it is the best guess for
+            // modes which are not known (yet)
+            GameType::Zen
+            | GameType::QuickPlay
+            | GameType::ExpertQuickPlay
+            | GameType::Unknown(_) => Self::standard(seed),
         }
     }
 
@@ -85,12 +138,37 @@ impl PieceQueue {
         Self::seeded_with_base(seed, base)
     }
 
+    /// Creates a piece queue which deals each piece twice per bag
+    pub fn fourteen_bag(seed: u64) -> Self {
+        use MinoVariant::*;
+        let base = Box::new([Z, L, O, S, I, J, T, Z, L, O, S, I, J, T]);
+        Self::seeded_with_base(seed, base)
+    }
+
     pub fn fortyline(seed: u64) -> Self {
         use MinoVariant::*;
         let base = Box::new([I, O, T, Z, J, L, S]);
         Self::seeded_with_base(seed, base)
     }
 
+    /// The number of bags needed to deal `pieces` pieces
+    fn bags_dealt(&self, pieces: usize) -> usize {
+        pieces.div_ceil(self.base.len())
+    }
+
+    /// The pieces which have been dealt, but not yet taken from the queue
+    pub fn upcoming(&self) -> impl Iterator<Item = MinoVariant> + '_ {
+        self.window.iter().copied()
+    }
+
+    /// The seed which recreates this queue with [Self::from_game] when the `listed` pieces before
+    /// it are the last ones dealt (such as the active piece followed by [Self::upcoming])
+    pub fn resume_seed(&self, listed: usize) -> u64 {
+        // each bag is shuffled with one number fewer than it has pieces
+        let draws = self.bags_dealt(listed) * (self.base.len() - 1);
+        self.rng.rewound(draws)
+    }
+
     /// Return the next piece held in the queue and generate more pieces if necessary
     pub fn pop(&mut self) -> MinoVariant {
         if self.window.is_empty() {
@@ -108,7 +186,9 @@ impl PieceQueue {
 
 #[cfg(test)]
 mod tests {
-    use super::Rng;
+    use itertools::Itertools;
+
+    use super::{PieceQueue, Rng};
 
     #[test]
     fn based() {
@@ -122,4 +202,14 @@ mod tests {
         assert_eq!(rng.shuffle_array(base), ['s', 'j', 't', 'o', 'l', 'i', 'z']);
         assert_eq!(rng.shuffle_array(base), ['l', 'j', 't', 'o', 's', 'i', 'z']);
     }
+
+    #[test]
+    fn fourteen_bag() {
+        let mut queue = PieceQueue::fourteen_bag(1742887628);
+        for _ in 0..3 {
+            let counts = (0..14).map(|_| queue.pop()).counts();
+            assert_eq!(counts.len(), 7);
+            assert!(counts.values().all(|&count| count == 2));
+        }
+    }
 }
@@ -0,0 +1,55 @@
+use tetrio_replay::reconstruct;
+use ttrm::event::EventData;
+use viewtris::action::ActionKind;
+
+#[test]
+fn league() {
+    let ttrm = ttrm::ttrm_from_slice(
+        include_bytes!("../../samples/HBSQabUhSS.ttrm"),
+        ttrm::ParseMode::Strict,
+    )
+    .unwrap();
+
+    let mut checked = 0;
+    for (round, set) in ttrm.data.iter().enumerate() {
+        for (player, replay) in set.replays.iter().enumerate() {
+            let actions = reconstruct(&ttrm.game_type, &replay.events).unwrap();
+            let total = |amount: fn(&ActionKind) -> u64| {
+                actions
+                    .iter()
+                    .map(|action| amount(&action.kind))
+                    .sum::<u64>()
+            };
+            let lines = total(|kind| matches!(kind, ActionKind::LineClear { .. }) as u64);
+            let attack = total(|kind| match *kind {
+                ActionKind::Attack { amount } => amount as u64,
+                _ => 0,
+            });
+            let cancelled = total(|kind| match *kind {
+                ActionKind::GarbageCancel { amount } => amount as u64,
+                _ => 0,
+            });
+
+            // as counted in the stats at the end of the replay
+            let stats = &replay
+                .events
+                .iter()
+                .find_map(|event| match &event.data {
+                    EventData::End { data } => data.export.as_ref(),
+                    _ => None,
+                })
+                .unwrap()
+                .stats;
+            // boards which are not reconstructed closely enough to clear the same lines cannot be
+            // expected to send the same attack
+            if lines != stats.lines {
+                continue;
+            }
+            checked += 1;
+            let at = format!("round {round} player {player}");
+            assert_eq!(attack, stats.garbage.attack, "{at}");
+            assert_eq!(attack - cancelled, stats.garbage.sent, "{at}");
+        }
+    }
+    assert!(checked >= 10, "only {checked} replays were checked");
+}
@@ -0,0 +1,154 @@
+use tetrio_replay::{extract, reconstruct, state_at};
+use ttrm::{
+    event::{EventData, EventFull, Key},
+    ParseMode, Ttr,
+};
+
+fn forty_line() -> Ttr<'static> {
+    ttrm::ttr_from_slice(include_bytes!("../../samples/_40l.ttr"), ParseMode::Strict)
+        .unwrap()
+        .into_owned()
+}
+
+/// The parts of a game state which are kept when a replay is cut
+fn summary(full: &EventFull) -> serde_json::Value {
+    serde_json::json!({
+        "board": full.game.board,
+        "bag": full.game.bag,
+        "hold": full.game.hold,
+        "seed": full.options.seed,
+        "lines": full.stats.lines,
+        "combo": full.stats.combo,
+        "btb": full.stats.btb,
+    })
+}
+
+#[test]
+fn extract_whole_game() {
+    let ttr = forty_line();
+    let frames = ttr.data.frames.as_u64().unwrap() as u32;
+    let extracted = extract(&ttr.game_type, &ttr.data, 0..frames).unwrap();
+
+    assert_eq!(ttrm::validate(&extracted), []);
+    assert_eq!(
+        format!("{:?}", reconstruct(&ttr.game_type, &ttr.data.events)),
+        format!("{:?}", reconstruct(&ttr.game_type, &extracted.events)),
+    );
+}
+
+#[test]
+fn truncated_opener() {
+    let ttr = forty_line();
+    let opener = extract(&ttr.game_type, &ttr.data, 0..200).unwrap();
+
+    assert_eq!(ttrm::validate(&opener), []);
+    assert_eq!(opener.frames.as_u64(), Some(201));
+    assert!(matches!(
+        opener.events.last().unwrap(),
+        ttrm::event::Event {
+            frame: 200,
+            data: EventData::End { .. }
+        }
+    ));
+
+    // the cut replay is still a valid ttr
+    let cut = Ttr {
+        data: opener,
+        ..ttr.clone()
+    };
+    let written = ttrm::ttr_to_vec(&cut).unwrap();
+    ttrm::ttr_from_slice(&written, ParseMode::Strict).unwrap();
+}
+
+#[test]
+fn extract_rest_of_game() {
+    let ttr = forty_line();
+    let frames = ttr.data.frames.as_u64().unwrap() as u32;
+
+    // cut just after a piece is dropped with nothing held, so that the next piece has only just
+    // appeared at the top of the board
+    let drop = ttr
+        .data
+        .events
+        .iter()
+        .filter(|event| {
+            matches!(event.data, EventData::KeyUp { ref key_event } if key_event.key == Key::HardDrop)
+        })
+        .map(|event| event.frame + 1)
+        .filter(|&frame| ttr.data.held_keys(frame).is_empty())
+        .nth(20)
+        .unwrap();
+
+    let rest = extract(&ttr.game_type, &ttr.data, drop..frames).unwrap();
+    assert_eq!(ttrm::validate(&rest), []);
+    assert_eq!(
+        summary(&state_at(&ttr.game_type, &ttr.data.events, drop).unwrap()),
+        summary(&state_at(&ttr.game_type, &rest.events, 0).unwrap()),
+    );
+
+    let last = frames - 1;
+    assert_eq!(
+        summary(&state_at(&ttr.game_type, &ttr.data.events, last).unwrap()),
+        summary(&state_at(&ttr.game_type, &rest.events, last - drop).unwrap()),
+    );
+}
+
+#[test]
+fn stats_at_end() {
+    let ttr = forty_line();
+    let frames = ttr.data.frames.as_u64().unwrap() as u32;
+    let end = ttr
+        .data
+        .events
+        .iter()
+        .find_map(|event| match event.data {
+            EventData::End { ref data } => data.export.as_ref(),
+            _ => None,
+        })
+        .unwrap();
+
+    let state = state_at(&ttr.game_type, &ttr.data.events, frames).unwrap();
+    assert_eq!(state.stats.lines, end.stats.lines);
+    assert_eq!(state.stats.combo, end.stats.combo);
+    assert_eq!(state.stats.btb, end.stats.btb);
+}
+
+#[test]
+fn extract_league_round() {
+    let ttrm = ttrm::ttrm_from_slice(
+        include_bytes!("../../samples/HBSQabUhSS.ttrm"),
+        ParseMode::Strict,
+    )
+    .unwrap();
+    let replay = &ttrm.data[0].replays[0];
+    let frames = 1000..2000;
+
+    // the frames recorded inside of interactions and targeting changes move along with the events
+    let inner_frames = |replay: &ttrm::Replay, start: u32| {
+        replay
+            .events
+            .iter()
+            .filter(|event| event.frame >= start && event.frame < start + frames.len() as u32)
+            .filter_map(|event| match event.data {
+                EventData::InGameEvent { ref event } => Some((
+                    event.frame.saturating_sub(start),
+                    event
+                        .data
+                        .sent_frame
+                        .as_u64()
+                        .unwrap()
+                        .saturating_sub(start.into()),
+                )),
+                EventData::Targets { ref data } => Some((data.frame.saturating_sub(start), 0)),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+    let extracted = extract(&ttrm.game_type, replay, frames.clone()).unwrap();
+    let expected = inner_frames(replay, frames.start);
+    assert!(expected.iter().any(|&(frame, _)| frame > 0));
+    assert_eq!(inner_frames(&extracted, 0), expected);
+
+    assert_eq!(ttrm::validate(&extracted), []);
+    reconstruct(&ttrm.game_type, &extracted.events).expect("Reconstruction step failed");
+}
@@ -0,0 +1,53 @@
+use tetrio_replay::reconstruct;
+use viewtris::{
+    action::ActionKind,
+    fumen::{decode, encode, game_pages, Page},
+    tetromino::Cell,
+};
+
+#[test]
+fn forty_line_game() {
+    let ttr = ttrm::ttr_from_slice(
+        include_bytes!("../../samples/_40l.ttr"),
+        ttrm::ParseMode::Strict,
+    )
+    .unwrap();
+    let actions = reconstruct(&ttr.game_type, &ttr.data.events).unwrap();
+    let pages = game_pages(&actions);
+
+    // a page for every piece placed, and one for the end of the game
+    assert_eq!(
+        pages.len() as u64,
+        ttr.end_context.pieces_placed + 1,
+        "pages for every piece"
+    );
+    assert!(pages[..pages.len() - 1]
+        .iter()
+        .all(|page| page.lock && page.active.is_some()));
+
+    let fumen = encode(&pages);
+    assert_eq!(decode(&fumen), Ok(pages.clone()));
+
+    // each page leaves behind the field the next one starts with, so only the pieces need to be
+    // written (once the hold, which is written as a comment, is left out)
+    let without_hold = pages
+        .iter()
+        .map(|page| Page {
+            hold: None,
+            ..page.clone()
+        })
+        .collect::<Vec<_>>();
+    assert!(encode(&without_hold).len() < pages.len() * 4 + 100);
+
+    let last_frame = actions.last().unwrap().frame;
+    let end = Page::from_actions(&actions, last_frame);
+    assert_eq!(end.field, pages.last().unwrap().field);
+    assert!(end
+        .field
+        .iter()
+        .flatten()
+        .all(|cell| !matches!(cell, Cell::Garbage)));
+    assert!(actions
+        .iter()
+        .any(|action| matches!(action.kind, ActionKind::LineClear { .. })));
+}
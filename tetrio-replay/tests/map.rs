@@ -0,0 +1,87 @@
+use tetrio_replay::{state_at, Map, MapError};
+use ttrm::ParseMode;
+use viewtris::tetromino::{Cell, MinoVariant};
+
+#[test]
+fn round_trip() {
+    let s =
+        "____________________________________________________________tt________####_#####?szjl?o";
+    let map = s.parse::<Map>().unwrap();
+
+    assert_eq!(map.board.len(), 8);
+    assert_eq!(map.board[6][1], Cell::Tetromino(MinoVariant::T));
+    assert_eq!(map.board[7][4], Cell::Empty);
+    assert_eq!(map.board[7][5], Cell::Garbage);
+    assert_eq!(
+        map.queue,
+        [
+            MinoVariant::S,
+            MinoVariant::Z,
+            MinoVariant::J,
+            MinoVariant::L
+        ]
+    );
+    assert_eq!(map.hold, Some(MinoVariant::O));
+    assert_eq!(map.to_string(), s);
+
+    // the queue and hold are optional
+    let board = "______####";
+    assert_eq!(board.parse::<Map>().unwrap().to_string(), board);
+    let hold_only = "______####??i";
+    assert_eq!(hold_only.parse::<Map>().unwrap().to_string(), hold_only);
+}
+
+#[test]
+fn width() {
+    let s = "i___________########_###";
+    let map = Map::parse(s, 12).unwrap();
+    assert_eq!(map.width(), 12);
+    assert_eq!(map.board.len(), 2);
+    assert_eq!(map.board[1][8], Cell::Empty);
+    assert_eq!(map.to_string(), s);
+
+    assert_eq!(
+        Map::parse(s, 10),
+        Err(MapError::UnevenBoard {
+            cells: 24,
+            width: 10
+        })
+    );
+}
+
+#[test]
+fn invalid() {
+    assert_eq!("____x_____".parse::<Map>(), Err(MapError::UnknownCell('x')));
+    assert_eq!(
+        "__________?sz#".parse::<Map>(),
+        Err(MapError::UnknownPiece('#'))
+    );
+    assert_eq!(
+        "__________?sz?ij".parse::<Map>(),
+        Err(MapError::MultipleHold)
+    );
+    assert_eq!(
+        "__________?sz?i?".parse::<Map>(),
+        Err(MapError::TooManySections)
+    );
+}
+
+#[test]
+fn dump_state() {
+    let ttr =
+        ttrm::ttr_from_slice(include_bytes!("../../samples/_40l.ttr"), ParseMode::Strict).unwrap();
+    let state = state_at(&ttr.game_type, &ttr.data.events, 600).unwrap();
+    let map = Map::from_game(&state.game);
+
+    assert_eq!(map.width(), 10);
+    assert_eq!(map.board.len(), state.game.board.len());
+    assert!(map.board.iter().flatten().any(|&cell| cell != Cell::Empty));
+
+    // the map is read back as the same state
+    let read = Map::parse(&map.to_string(), 10).unwrap();
+    assert_eq!(read, map);
+    let mut game = state.game.clone();
+    read.apply(&mut game);
+    assert_eq!(game.board, state.game.board);
+    assert_eq!(game.bag, state.game.bag);
+}
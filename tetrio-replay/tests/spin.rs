@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+use tetrio_replay::reconstruct;
+use viewtris::{
+    action::ActionKind,
+    tables::damage_table::Spin,
+    tetromino::{Cell, MinoVariant},
+};
+
+/// The number of T-spins which cleared each number of lines
+fn t_spins(bytes: &[u8]) -> HashMap<usize, usize> {
+    let ttr = ttrm::ttr_from_slice(bytes, ttrm::ParseMode::Strict).unwrap();
+    let actions = reconstruct(&ttr.game_type, &ttr.data.events).unwrap();
+
+    // the lines cleared by each piece, if it was a T-spin
+    let mut locks = Vec::new();
+    let mut piece = None;
+    for action in &actions {
+        match action.kind {
+            ActionKind::Cell {
+                kind: Cell::Tetromino(variant),
+                ..
+            } => piece = Some(variant),
+            ActionKind::Lock { spin } => {
+                locks.push((piece == Some(MinoVariant::T) && spin == Spin::Full).then_some(0))
+            }
+            ActionKind::LineClear { .. } => {
+                if let Some(Some(lines)) = locks.last_mut() {
+                    *lines += 1;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    locks
+        .into_iter()
+        .flatten()
+        .fold(HashMap::new(), |mut t_spins, lines| {
+            *t_spins.entry(lines).or_default() += 1;
+            t_spins
+        })
+}
+
+#[test]
+fn blitz() {
+    let t_spins = t_spins(include_bytes!("../../samples/blitz.ttr"));
+    // as counted in the stats of the replay, which has 21 T-spins of which 10 are doubles
+    assert_eq!(t_spins.values().sum::<usize>(), 21);
+    assert_eq!(t_spins[&2], 10);
+    assert_eq!(t_spins.get(&3), None);
+}
+
+#[test]
+fn zbrachi_standard() {
+    let t_spins = t_spins(include_bytes!("../../samples/zbrachi_standard.ttr"));
+    assert_eq!(t_spins, HashMap::from([(2, 4)]));
+}
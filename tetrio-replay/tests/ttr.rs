@@ -9,7 +9,7 @@ fn reconstruct_from_bytes(bytes: &[u8], write_to: &str) -> Result<(), Vec<Action
         .expect("TTR parsing is not working correctly, check tests in ttr crate");
 
     let action_list =
-        reconstruct(ttr.game_type, &ttr.data.events).expect("Reconstruction step failed");
+        reconstruct(&ttr.game_type, &ttr.data.events).expect("Reconstruction step failed");
 
     OpenOptions::new()
         .create(true)
@@ -53,3 +53,67 @@ ttr_test!(garbage);
 ttr_test!(_40l);
 
 ttr_test!(blitz);
+
+#[test]
+fn other_game_types() {
+    for (name, game_type) in [
+        ("zen", ttrm::GameType::Zen),
+        (
+            "some_new_mode",
+            ttrm::GameType::Unknown("some_new_mode".to_owned()),
+        ),
+    ] {
+        let bytes = std::str::from_utf8(include_bytes!("../../samples/_40l.ttr"))
+            .unwrap()
+            .replace(r#""gametype":"40l""#, &format!(r#""gametype":"{name}""#));
+        let ttr = serde_json::from_slice::<ttrm::Ttr>(bytes.as_bytes()).unwrap();
+
+        assert_eq!(ttr.game_type, game_type);
+        reconstruct(&ttr.game_type, &ttr.data.events).expect("Reconstruction step failed");
+    }
+}
+
+#[test]
+fn unknown_piece() {
+    let bytes = std::str::from_utf8(include_bytes!("../../samples/_40l.ttr"))
+        .unwrap()
+        .replacen(r#""bag":["j""#, r#""bag":["x""#, 1);
+    let ttr = ttrm::ttr_from_slice(bytes.as_bytes(), ttrm::ParseMode::Strict).unwrap();
+
+    let error = reconstruct(&ttr.game_type, &ttr.data.events).unwrap_err();
+    assert_eq!(error, "`x` is not a piece");
+}
+
+#[test]
+fn unsupported_rules() {
+    let sample = std::str::from_utf8(include_bytes!("../../samples/zbrachi_standard.ttr")).unwrap();
+    for (from, to, expected) in [
+        (
+            r#""kickset":"SRS+""#,
+            r#""kickset":"ARS""#,
+            "the kickset `ARS` is not supported",
+        ),
+        (
+            r#""bagtype":"7bag""#,
+            r#""bagtype":"classic""#,
+            "the bag type `classic` is not supported",
+        ),
+    ] {
+        let bytes = sample.replace(from, to);
+        let ttr = ttrm::ttr_from_slice(bytes.as_bytes(), ttrm::ParseMode::Strict).unwrap();
+
+        let error = reconstruct(&ttr.game_type, &ttr.data.events).unwrap_err();
+        assert_eq!(error, expected);
+    }
+
+    // rules which can be simulated are played back instead
+    for (from, to) in [
+        (r#""kickset":"SRS+""#, r#""kickset":"SRS""#),
+        (r#""bagtype":"7bag""#, r#""bagtype":"14-bag""#),
+    ] {
+        let bytes = sample.replace(from, to);
+        let ttr = ttrm::ttr_from_slice(bytes.as_bytes(), ttrm::ParseMode::Strict).unwrap();
+
+        reconstruct(&ttr.game_type, &ttr.data.events).expect("Reconstruction step failed");
+    }
+}
@@ -1,6 +1,6 @@
 #![allow(non_snake_case)]
 
-use std::fs::OpenOptions;
+use std::fs::{File, OpenOptions};
 
 use std::io::Write;
 use tetrio_replay::reconstruct;
@@ -10,7 +10,7 @@ use viewtris::action::Action;
 
 fn reconstruct_from_events(
     events: &[Event],
-    game_type: GameType,
+    game_type: &GameType,
     write_to: &str,
 ) -> Result<(), Vec<Action>> {
     let action_list = reconstruct(game_type, events).expect("Reconstruction step failed");
@@ -45,7 +45,7 @@ macro_rules! ttrm_test {
                 for (j, replay) in data.replays.iter().enumerate() {
                     let write_to = format!(concat!("test_out/", stringify!($name), "_{}_{}.out"), i, j);
 
-                    if let Err(action_list) = reconstruct_from_events(&replay.events, ttr.game_type, &write_to) {
+                    if let Err(action_list) = reconstruct_from_events(&replay.events, &ttr.game_type, &write_to) {
                         println!(concat!(
                             "Test ",
                             stringify!($name),
@@ -61,3 +61,31 @@ macro_rules! ttrm_test {
 }
 
 ttrm_test!(HBSQabUhSS);
+
+#[test]
+fn streamed_reconstruction() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../samples/HBSQabUhSS.ttrm");
+    let ttrm = ttrm::ttrm_from_reader(File::open(path).unwrap(), ttrm::ParseMode::Strict).unwrap();
+    let streamed = tetrio_replay::reconstruct_ttrm_stream(File::open(path).unwrap())
+        .unwrap()
+        .map(Result::unwrap)
+        .collect::<Vec<_>>();
+
+    let replays = ttrm
+        .data
+        .iter()
+        .enumerate()
+        .flat_map(|(round, set)| {
+            set.replays
+                .iter()
+                .enumerate()
+                .map(move |(player, replay)| (round, player, replay))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(streamed.len(), replays.len());
+    for (streamed, (round, player, replay)) in streamed.iter().zip(replays) {
+        assert_eq!((streamed.round, streamed.player), (round, player));
+        let actions = reconstruct(&ttrm.game_type, &replay.events).unwrap();
+        assert_eq!(format!("{actions:?}"), format!("{:?}", streamed.actions));
+    }
+}
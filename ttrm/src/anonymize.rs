@@ -0,0 +1,191 @@
+//! Removing the identities of players from replays, so that they can be shared publicly.
+
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    event::{EventData, EventFull},
+    Replay, Ttr, Ttrm, User,
+};
+
+/// The number of (hexadecimal) digits in a user id
+const USER_ID_LENGTH: usize = 24;
+
+/// The identity given to a player in place of their own
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pseudonym {
+    pub username: String,
+    pub user_id: String,
+}
+
+impl Pseudonym {
+    /// The `n`th pseudonym handed out, such as `player1` for the first
+    fn numbered(n: usize) -> Self {
+        Self {
+            username: format!("player{n}"),
+            user_id: format!("{n:0USER_ID_LENGTH$x}"),
+        }
+    }
+}
+
+/// A player who has been given a pseudonym. Either of the real user id or username may be missing
+/// if only the other has been seen (for example, interactions only name the sender).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Player {
+    user_id: Option<String>,
+    username: Option<String>,
+    pseudonym: Pseudonym,
+}
+
+/// Replaces the usernames and user ids of players with pseudonyms.
+///
+/// Each player keeps the same pseudonym for as long as the same anonymizer is used, so anonymizing
+/// several replays with one anonymizer keeps players recognizable across them. The anonymizer can
+/// also be saved (it implements [Serialize]) to keep pseudonyms stable between runs, but since it
+/// holds the real identity behind each pseudonym, it should not be shared along with the replays.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Anonymizer {
+    players: Vec<Player>,
+}
+
+impl Anonymizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lists the real user id and username of each player, along with their pseudonym
+    pub fn pseudonyms(&self) -> impl Iterator<Item = (Option<&str>, Option<&str>, &Pseudonym)> {
+        self.players.iter().map(|player| {
+            (
+                player.user_id.as_deref(),
+                player.username.as_deref(),
+                &player.pseudonym,
+            )
+        })
+    }
+
+    /// Finds (or hands out) the pseudonym of a player, remembering whichever of their user id and
+    /// username were not yet known
+    fn pseudonym(&mut self, user_id: Option<&str>, username: Option<&str>) -> &Pseudonym {
+        let found = self.players.iter().position(|player| {
+            let same_id = matches!((user_id, &player.user_id), (Some(a), Some(b)) if a == b);
+            // usernames are sometimes written in uppercase (as in the options of a game)
+            let same_name = matches!(
+                (username, &player.username),
+                (Some(a), Some(b)) if a.eq_ignore_ascii_case(b)
+            );
+            same_id || same_name
+        });
+
+        let index = found.unwrap_or_else(|| {
+            self.players.push(Player {
+                user_id: None,
+                username: None,
+                pseudonym: Pseudonym::numbered(self.players.len() + 1),
+            });
+            self.players.len() - 1
+        });
+
+        let player = &mut self.players[index];
+        if player.user_id.is_none() {
+            player.user_id = user_id.map(str::to_owned);
+        }
+        if player.username.is_none() {
+            player.username = username.map(|name| name.to_lowercase());
+        }
+        &player.pseudonym
+    }
+
+    fn username(&mut self, username: &str) -> String {
+        let pseudonym = &self.pseudonym(None, Some(username)).username;
+        if username.chars().any(|c| c.is_lowercase()) {
+            pseudonym.clone()
+        } else {
+            pseudonym.to_uppercase()
+        }
+    }
+
+    fn user_id(&mut self, user_id: &str) -> String {
+        self.pseudonym(Some(user_id), None).user_id.clone()
+    }
+
+    /// Targets in full events are a user id followed by some number, so only the user id is
+    /// replaced
+    fn target(&mut self, target: &str) -> String {
+        match target.get(..USER_ID_LENGTH) {
+            Some(user_id) => self.user_id(user_id) + &target[USER_ID_LENGTH..],
+            None => self.user_id(target),
+        }
+    }
+
+    fn user(&mut self, user: &mut User) {
+        let pseudonym = self.pseudonym(Some(&user.user_id), Some(&user.username));
+        user.user_id = Cow::Owned(pseudonym.user_id.clone());
+        user.username = Cow::Owned(pseudonym.username.clone());
+    }
+
+    fn full(&mut self, full: &mut EventFull) {
+        if let Some(ref mut name) = full.killer.name {
+            *name = Cow::Owned(self.username(name));
+        }
+        if let Some(ref mut username) = full.options.username {
+            *username = self.username(username);
+        }
+        for target in &mut full.targets {
+            *target = Cow::Owned(self.target(target));
+        }
+    }
+
+    fn replay(&mut self, replay: &mut Replay) {
+        for event in &mut replay.events {
+            match event.data {
+                EventData::Full { ref mut data } => self.full(data),
+                EventData::End { ref mut data } => {
+                    if let Some(ref mut export) = data.export {
+                        self.full(export)
+                    }
+                }
+                EventData::Targets { ref mut data } => {
+                    for target in &mut data.targets {
+                        *target = Cow::Owned(self.user_id(target));
+                    }
+                }
+                EventData::InGameEvent { ref mut event } => {
+                    event.data.sender = self.username(&event.data.sender);
+                }
+                EventData::Start {} | EventData::KeyDown { .. } | EventData::KeyUp { .. } => (),
+            }
+        }
+    }
+
+    /// Replaces every identifying string of a singleplayer replay. The ids of the replay itself are
+    /// removed, since they can be used to find the original on tetrio.
+    pub fn anonymize_ttr(&mut self, ttr: &mut Ttr) {
+        ttr.id = None;
+        ttr.short_id = None;
+        self.user(&mut ttr.user);
+        self.replay(&mut ttr.data);
+    }
+
+    /// Replaces every identifying string of a multiplayer replay. The ids of the replay itself are
+    /// blanked out, since they can be used to find the original on tetrio.
+    pub fn anonymize_ttrm(&mut self, ttrm: &mut Ttrm) {
+        ttrm.id = Cow::Owned("0".repeat(USER_ID_LENGTH));
+        ttrm.short_id = Cow::Borrowed("anonymous");
+
+        // players are named in the order they are listed in the match results, before any of the
+        // rounds are looked at
+        for player in &mut ttrm.end_context {
+            self.user(&mut player.user);
+        }
+        for set in &mut ttrm.data {
+            for board in &mut set.boards {
+                self.user(&mut board.user);
+            }
+            for replay in &mut set.replays {
+                self.replay(replay);
+            }
+        }
+    }
+}
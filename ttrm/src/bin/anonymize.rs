@@ -0,0 +1,95 @@
+//! Removes the names of players from a ttr or ttrm file.
+//!
+//! Usage: `anonymize [--mapping <mapping.json>] <input> <output>`
+//!
+//! If a mapping file is given, the pseudonyms in it are reused (and new ones are added to it), so
+//! that players keep the same pseudonym over many runs. The mapping file contains the real names of
+//! the players, so it should be kept private.
+
+use std::{path::PathBuf, process::ExitCode};
+
+use ttrm::{anonymize::Anonymizer, compression, ParseMode};
+
+const USAGE: &str = "usage: anonymize [--mapping <mapping.json>] <input> <output>";
+
+struct Args {
+    mapping: Option<PathBuf>,
+    input: PathBuf,
+    output: PathBuf,
+}
+
+fn parse_args() -> Option<Args> {
+    let mut args = std::env::args_os().skip(1);
+    let mut mapping = None;
+    let mut paths = Vec::new();
+
+    while let Some(arg) = args.next() {
+        if arg == "--mapping" {
+            mapping = Some(PathBuf::from(args.next()?));
+        } else {
+            paths.push(PathBuf::from(arg));
+        }
+    }
+
+    let [input, output]: [PathBuf; 2] = paths.try_into().ok()?;
+    Some(Args {
+        mapping,
+        input,
+        output,
+    })
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let mut anonymizer = match args.mapping {
+        Some(ref mapping) if mapping.exists() => {
+            let file =
+                std::fs::read(mapping).map_err(|e| format!("could not read mapping: {e}"))?;
+            serde_json::from_slice(&file).map_err(|e| format!("invalid mapping: {e}"))?
+        }
+        _ => Anonymizer::new(),
+    };
+
+    let input =
+        std::fs::read(&args.input).map_err(|e| format!("could not read the replay: {e}"))?;
+    // the output is written uncompressed, whether or not the input was compressed
+    let output = match compression::replay_extension(&args.input) {
+        Some("ttr") => {
+            let mut ttr = ttrm::ttr_from_slice(&input, ParseMode::Lenient)
+                .map_err(|e| format!("could not parse the replay: {e}"))?;
+            anonymizer.anonymize_ttr(&mut ttr);
+            ttrm::ttr_to_vec(&ttr)
+        }
+        Some("ttrm") => {
+            let mut ttrm = ttrm::ttrm_from_slice(&input, ParseMode::Lenient)
+                .map_err(|e| format!("could not parse the replay: {e}"))?;
+            anonymizer.anonymize_ttrm(&mut ttrm);
+            ttrm::ttrm_to_vec(&ttrm)
+        }
+        _ => return Err("expected a ttr or ttrm file".to_owned()),
+    }
+    .map_err(|e| format!("could not write the replay: {e}"))?;
+
+    std::fs::write(&args.output, output).map_err(|e| format!("could not write the replay: {e}"))?;
+
+    if let Some(mapping) = args.mapping {
+        let file = serde_json::to_vec_pretty(&anonymizer).map_err(|e| e.to_string())?;
+        std::fs::write(mapping, file).map_err(|e| format!("could not write mapping: {e}"))?;
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let Some(args) = parse_args() else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
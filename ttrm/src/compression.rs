@@ -0,0 +1,82 @@
+//! Reading replays which have been compressed, such as `.ttr.gz` or `.ttrm.zst` files. The format
+//! is recognized from the first bytes of the file rather than from its name.
+
+use std::{
+    borrow::Cow,
+    io::{self, Read},
+    path::Path,
+};
+
+use flate2::read::MultiGzDecoder;
+use ruzstd::decoding::StreamingDecoder;
+
+use crate::Error;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// The ways a replay file may be compressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Recognizes the compression of a file from its first few bytes
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(GZIP_MAGIC) {
+            Self::Gzip
+        } else if bytes.starts_with(ZSTD_MAGIC) {
+            Self::Zstd
+        } else {
+            Self::None
+        }
+    }
+
+    /// The file extensions used for files compressed this way, such as `gz` in `replay.ttr.gz`
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Compression::None => &[],
+            Compression::Gzip => &["gz", "gzip"],
+            Compression::Zstd => &["zst", "zstd"],
+        }
+    }
+}
+
+/// The extension of a replay file, looking past any extension added by compressing it (so that
+/// `replay.ttr.gz` gives `ttr`)
+pub fn replay_extension(path: &Path) -> Option<&str> {
+    let extension = path.extension()?.to_str()?;
+    let compressed = [Compression::Gzip, Compression::Zstd]
+        .iter()
+        .any(|compression| compression.extensions().contains(&extension));
+    if compressed {
+        Path::new(path.file_stem()?).extension()?.to_str()
+    } else {
+        Some(extension)
+    }
+}
+
+/// Decompresses the bytes of a file if they are compressed, or otherwise gives them back as they
+/// are
+pub fn decompress(bytes: &[u8]) -> Result<Cow<'_, [u8]>, Error> {
+    let mut decompressed = Vec::new();
+    match Compression::detect(bytes) {
+        Compression::None => return Ok(Cow::Borrowed(bytes)),
+        Compression::Gzip => {
+            MultiGzDecoder::new(bytes).read_to_end(&mut decompressed)?;
+        }
+        Compression::Zstd => {
+            // files can be made up of several frames, which are decompressed one after another
+            let mut input = bytes;
+            while !input.is_empty() {
+                StreamingDecoder::new(&mut input)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+                    .read_to_end(&mut decompressed)?;
+            }
+        }
+    }
+    Ok(Cow::Owned(decompressed))
+}
@@ -0,0 +1,313 @@
+//! Converting between singleplayer replays and multiplayer replays, so that collections of either
+//! can be kept in one format.
+
+use std::{borrow::Cow, fmt::Display};
+
+use crate::{
+    event::{AggregateStats, EventData, EventFull, Stats},
+    Board, EndContext, ExtraPoints, ExtraPointsPerRound, GameType, PlayerEndContext, Points,
+    Replay, ReplaySet, Ttr, Ttrm, User,
+};
+
+/// Why replays could not be converted
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConvertError {
+    /// There were no replays to put into a multiplayer replay
+    NoReplays,
+    /// The replays were of different game types, but a multiplayer replay has only one
+    MixedGameTypes { first: GameType, other: GameType },
+    /// A replay of a multiplayer replay has no board naming its player
+    MissingPlayer { round: usize, player: usize },
+    /// A replay of a multiplayer replay has no state to take its statistics from
+    MissingStats { round: usize, player: usize },
+}
+
+impl Display for ConvertError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConvertError::NoReplays => write!(f, "there are no replays to convert"),
+            ConvertError::MixedGameTypes { first, other } => write!(
+                f,
+                "replays of `{first}` cannot be kept together with replays of `{other}`"
+            ),
+            ConvertError::MissingPlayer { round, player } => write!(
+                f,
+                "the replay of player {player} in round {round} does not belong to any player"
+            ),
+            ConvertError::MissingStats { round, player } => write!(
+                f,
+                "the replay of player {player} in round {round} has no statistics"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConvertError {}
+
+impl<'a> Replay<'a> {
+    /// The latest state of the game described by the replay: the state exported when the game
+    /// ended, or otherwise the last full event
+    pub fn final_state(&self) -> Option<&EventFull<'a>> {
+        self.events.iter().rev().find_map(|event| match event.data {
+            EventData::End { ref data } => data.export.as_ref(),
+            EventData::Full { ref data } => Some(data),
+            _ => None,
+        })
+    }
+
+    /// The name the player of the replay played under, as given in the options of the game. This
+    /// may be written in uppercase.
+    pub fn username(&self) -> Option<&str> {
+        self.events.iter().find_map(|event| match event.data {
+            EventData::Full { ref data } => data.options.username.as_deref(),
+            _ => None,
+        })
+    }
+}
+
+impl EndContext {
+    /// Fills in the end context of a singleplayer replay from the statistics of a game which ended
+    /// on `end_frame`
+    pub fn from_stats(stats: &Stats, end_frame: u32, game_type: GameType) -> Self {
+        Self {
+            seed: stats.seed,
+            lines: stats.lines,
+            level_lines: stats.level_lines,
+            level_lines_needed: stats.level_lines_needed,
+            inputs: stats.inputs,
+            holds: stats.holds,
+            time: stats.time.clone(),
+            score: stats.score,
+            zen_level: stats.zen_level,
+            zen_progress: stats.zen_progress,
+            level: stats.level,
+            combo: stats.combo,
+            combo_power: stats.combo_power,
+            top_combo: stats.top_combo,
+            btb: stats.btb,
+            top_b2b: stats.top_b2b,
+            current_b2b_power: stats.current_b2b_power.unwrap_or(0),
+            tspins: stats.tspins,
+            pieces_placed: stats.pieces_placed,
+            clears: stats.clears.clone(),
+            garbage: stats.garbage.clone(),
+            kills: stats.kills,
+            finesse: stats.finesse.clone(),
+            // the game runs at 60 frames per second
+            final_time: end_frame as f64 * 1000. / 60.,
+            game_type: Some(game_type),
+        }
+    }
+}
+
+/// The points scored in a single round, as tetrio lists them in the end context of a match: attack
+/// per minute, pieces per second and versus score. These are exported along with the state of the
+/// game when it ends, but can otherwise be worked out from the end context.
+fn round_points(ttr: &Ttr) -> [f64; 3] {
+    let exported = ttr.data.events.last().and_then(|event| match event.data {
+        EventData::End { ref data } => data.export.as_ref(),
+        _ => None,
+    });
+    if let Some(state) = exported {
+        let AggregateStats { apm, pps, vsscore } = &state.aggregate_stats;
+        return [apm, pps, vsscore].map(|points| points.as_f64().unwrap_or(0.));
+    }
+
+    let end_context = &ttr.end_context;
+    let seconds = end_context.final_time / 1000.;
+    if seconds <= 0. {
+        return [0.; 3];
+    }
+    let attack = end_context.garbage.attack as f64;
+    let cleared = end_context.garbage.cleared as f64;
+    [
+        attack / seconds * 60.,
+        end_context.pieces_placed as f64 / seconds,
+        (attack + cleared) / seconds * 100.,
+    ]
+}
+
+/// A player of a match being put together from singleplayer replays
+struct MatchPlayer<'a> {
+    user: User<'a>,
+    /// The points scored in each round (see [round_points]), if the player played in it
+    rounds: Vec<Option<[f64; 3]>>,
+    wins: u32,
+    inputs: u64,
+    pieces_placed: u64,
+}
+
+impl<'a> MatchPlayer<'a> {
+    fn end_context(self, natural_order: usize) -> PlayerEndContext<'a> {
+        // rounds the player did not play in are scored as zero, but are not averaged
+        let per_round = |score: usize| -> Vec<f64> {
+            self.rounds
+                .iter()
+                .map(|points| points.map_or(0., |points| points[score]))
+                .collect()
+        };
+        let average = |score: usize| -> f64 {
+            let played = self.rounds.iter().flatten().collect::<Vec<_>>();
+            if played.is_empty() {
+                0.
+            } else {
+                played.iter().map(|points| points[score]).sum::<f64>() / played.len() as f64
+            }
+        };
+
+        PlayerEndContext {
+            natural_order: natural_order as u32,
+            active: true,
+            wins: self.wins,
+            points: Points {
+                wins: self.wins,
+                apm: average(0),
+                pps: average(1),
+                extra: ExtraPoints { vs: average(2) },
+                apm_per_round: per_round(0),
+                pps_per_round: per_round(1),
+                extra_per_round: ExtraPointsPerRound { vs: per_round(2) },
+            },
+            inputs: self.inputs,
+            pieces_placed: self.pieces_placed,
+            user: self.user,
+        }
+    }
+}
+
+impl<'a> Ttrm<'a> {
+    /// Gathers singleplayer replays into a multiplayer replay, where each replay is a round of its
+    /// own. The replays must all be of the same game type.
+    ///
+    /// The results of the match are worked out from the end context of each replay, with a round
+    /// counted as won if its game was successful (such as by clearing 40 lines). Since the match was
+    /// never played on tetrio, it has no ids of its own.
+    pub fn from_ttrs(ttrs: impl IntoIterator<Item = Ttr<'a>>) -> Result<Self, ConvertError> {
+        let ttrs = ttrs.into_iter().collect::<Vec<_>>();
+        let first = ttrs.first().ok_or(ConvertError::NoReplays)?;
+        if let Some(other) = ttrs.iter().find(|ttr| ttr.game_type != first.game_type) {
+            return Err(ConvertError::MixedGameTypes {
+                first: first.game_type.clone(),
+                other: other.game_type.clone(),
+            });
+        }
+
+        let successes = ttrs
+            .iter()
+            .map(|ttr| ttr.data.final_state().is_some_and(|state| state.successful))
+            .collect::<Vec<_>>();
+
+        let mut players: Vec<MatchPlayer> = Vec::new();
+        for (round, ttr) in ttrs.iter().enumerate() {
+            let index = players
+                .iter()
+                .position(|player| player.user.user_id == ttr.user.user_id)
+                .unwrap_or_else(|| {
+                    players.push(MatchPlayer {
+                        user: ttr.user.clone(),
+                        rounds: vec![None; ttrs.len()],
+                        wins: 0,
+                        inputs: 0,
+                        pieces_placed: 0,
+                    });
+                    players.len() - 1
+                });
+            let player = &mut players[index];
+            player.rounds[round] = Some(round_points(ttr));
+            player.wins += successes[round] as u32;
+            player.inputs += ttr.end_context.inputs;
+            player.pieces_placed += ttr.end_context.pieces_placed;
+        }
+        let end_context = players
+            .into_iter()
+            .enumerate()
+            .map(|(natural_order, player)| player.end_context(natural_order))
+            .collect();
+
+        let game_type = first.game_type.clone();
+        let timestamp = first.timestamp.clone();
+        let data = ttrs
+            .into_iter()
+            .zip(successes)
+            .map(|(ttr, success)| ReplaySet {
+                boards: vec![Board {
+                    active: false,
+                    success,
+                    user: ttr.user,
+                    // winners are listed with 2, and everyone else with 1
+                    winning: if success { 2 } else { 1 }.into(),
+                }],
+                replays: vec![ttr.data],
+            })
+            .collect();
+
+        Ok(Ttrm {
+            id: Cow::Owned("0".repeat(24)),
+            back: Cow::Borrowed(""),
+            data,
+            end_context,
+            force_style: Cow::Borrowed(""),
+            game_type,
+            is_multi: false,
+            short_id: Cow::Borrowed(""),
+            timestamp,
+            verified: false,
+        })
+    }
+
+    /// Splits a multiplayer replay into a singleplayer replay for each player of each round,
+    /// grouped by round.
+    ///
+    /// Each replay is matched to its player by the username it was played under, and takes its end
+    /// context from the state of the game when it ended. Every round is given the time the match
+    /// was played, as the times of the rounds themselves are not known.
+    pub fn into_ttrs(self) -> Result<Vec<Vec<Ttr<'a>>>, ConvertError> {
+        let game_type = self.game_type;
+        let timestamp = self.timestamp;
+
+        self.data
+            .into_iter()
+            .enumerate()
+            .map(|(round, set)| {
+                let ReplaySet { boards, replays } = set;
+                replays
+                    .into_iter()
+                    .enumerate()
+                    .map(|(player, replay)| {
+                        let board = replay
+                            .username()
+                            .and_then(|username| {
+                                boards.iter().find(|board| {
+                                    board.user.username.eq_ignore_ascii_case(username)
+                                })
+                            })
+                            .or_else(|| boards.get(player))
+                            .ok_or(ConvertError::MissingPlayer { round, player })?;
+                        let stats = &replay
+                            .final_state()
+                            .ok_or(ConvertError::MissingStats { round, player })?
+                            .stats;
+                        let end_frame = replay.events.last().map_or(0, |event| event.frame);
+
+                        Ok(Ttr {
+                            id: None,
+                            short_id: None,
+                            user: board.user.clone(),
+                            end_context: EndContext::from_stats(
+                                stats,
+                                end_frame,
+                                game_type.clone(),
+                            ),
+                            timestamp: timestamp.clone(),
+                            game_type: game_type.clone(),
+                            custom_type: None,
+                            modified_time: None,
+                            verified: None,
+                            data: replay,
+                        })
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
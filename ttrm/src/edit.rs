@@ -0,0 +1,140 @@
+//! Cutting replays down to part of a game.
+
+use std::ops::Range;
+
+use serde_json::Number;
+
+use crate::{
+    event::{Event, EventData, EventEnd, EventFull, Key, KeyEvent},
+    Replay,
+};
+
+/// A key event at the very start of a frame
+fn key_event(key: Key) -> KeyEvent {
+    KeyEvent {
+        key,
+        subframe: 0.into(),
+        hoisted: None,
+    }
+}
+
+/// Moves `event` back by `start` frames, including the frames recorded inside of its data
+fn rebase<'a>(event: &Event<'a>, start: u32) -> Event<'a> {
+    let mut data = event.data.clone();
+    match data {
+        EventData::Targets { ref mut data } => data.frame = data.frame.saturating_sub(start),
+        EventData::InGameEvent { ref mut event } => {
+            event.frame = event.frame.saturating_sub(start);
+            let sent_frame = &mut event.data.sent_frame;
+            if let Some(frame) = sent_frame.as_u64() {
+                *sent_frame = frame.saturating_sub(start.into()).into();
+            } else if let Some(frame) = sent_frame.as_f64() {
+                *sent_frame = Number::from_f64((frame - f64::from(start)).max(0.))
+                    .unwrap_or_else(|| 0.into());
+            }
+        }
+        _ => (),
+    }
+    Event {
+        frame: event.frame - start,
+        data,
+    }
+}
+
+impl<'a> Replay<'a> {
+    /// Lists the keys held down at the start of `frame`, in the order they were pressed
+    pub fn held_keys(&self, frame: u32) -> Vec<Key> {
+        let mut held = Vec::new();
+        for event in self.events.iter().take_while(|event| event.frame < frame) {
+            match event.data {
+                EventData::KeyDown { ref key_event } if !held.contains(&key_event.key) => {
+                    held.push(key_event.key)
+                }
+                EventData::KeyUp { ref key_event } => held.retain(|&key| key != key_event.key),
+                _ => (),
+            }
+        }
+        held
+    }
+
+    /// Cuts the replay off at `frame`, removing every event from that frame onwards. Keys which are
+    /// still held are released and the game is ended on that frame instead, so that the replay
+    /// stays consistent (see [crate::validate]). A replay which ends before `frame` is left as is.
+    pub fn truncate(&mut self, frame: u32) {
+        let Some(cut) = self.events.iter().position(|event| event.frame >= frame) else {
+            return;
+        };
+        let held = self.held_keys(frame);
+        self.events.truncate(cut);
+        self.end(frame, held);
+    }
+
+    /// Ends the game on `frame`, releasing the keys which are still `held`
+    fn end(&mut self, frame: u32, held: Vec<Key>) {
+        self.events.extend(held.into_iter().map(|key| Event {
+            frame,
+            data: EventData::KeyUp {
+                key_event: key_event(key),
+            },
+        }));
+        self.events.push(Event {
+            frame,
+            data: EventData::End {
+                data: Box::new(EventEnd {
+                    reason: None,
+                    export: None,
+                }),
+            },
+        });
+        self.frames = (frame + 1).into();
+    }
+
+    /// Extracts the events on `frames` into a replay of their own, with frames counted from the
+    /// start of the range. This includes the frames recorded inside of targeting changes and
+    /// interactions, which are kept at 0 if they came before the range.
+    ///
+    /// Since the new replay does not begin at the start of the game, `full` takes the place of the
+    /// original full event, and must describe the state of the game at the start of the range
+    /// (`tetrio-replay` can simulate the game to find it). Keys held at the start of the range are
+    /// pressed on the first frame, and the end of the range is cut off as with [Self::truncate].
+    pub fn extract(&self, frames: Range<u32>, full: EventFull<'a>) -> Replay<'a> {
+        let start = frames.start;
+        let mut events = vec![
+            Event {
+                frame: 0,
+                data: EventData::Start {},
+            },
+            Event {
+                frame: 0,
+                data: EventData::Full {
+                    data: Box::new(full),
+                },
+            },
+        ];
+        events.extend(self.held_keys(start).into_iter().map(|key| Event {
+            frame: 0,
+            data: EventData::KeyDown {
+                key_event: key_event(key),
+            },
+        }));
+        events.extend(
+            self.events
+                .iter()
+                .filter(|event| frames.contains(&event.frame))
+                .filter(|event| !matches!(event.data, EventData::Start {} | EventData::Full { .. }))
+                .map(|event| rebase(event, start)),
+        );
+
+        let last_frame = events.last().map_or(0, |event| event.frame);
+        let mut replay = Replay {
+            events,
+            frames: (last_frame + 1).into(),
+        };
+        if self.events.iter().any(|event| event.frame >= frames.end) {
+            let end = frames.end.saturating_sub(start);
+            let held = replay.held_keys(end);
+            replay.end(end, held);
+        }
+        replay
+    }
+}
@@ -0,0 +1,225 @@
+use std::fmt::{Display, Write};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::event::{EventFull, InteractionContainer, KeyEvent, Targets};
+
+/// How many characters of the offending value are kept when reporting an error
+const VALUE_PREVIEW_LENGTH: usize = 80;
+
+/// An error encountered while reading a replay, along with where in the replay it was found
+#[derive(Debug)]
+pub struct Error {
+    /// The path to the value which could not be parsed, such as
+    /// `data[1].replays[0].events[4123].data.options.garbagecap`. Empty if the location is not
+    /// known (for example, if the file is not valid json).
+    pub path: String,
+    /// The frame of the event the error was found in, if it was found in an event
+    pub frame: Option<u32>,
+    /// The (possibly shortened) json of the value which could not be parsed
+    pub value: Option<String>,
+    pub inner: serde_json::Error,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.inner)?;
+        if !self.path.is_empty() {
+            write!(f, ", at `{}`", self.path)?;
+        }
+        if let Some(frame) = self.frame {
+            write!(f, ", on frame {frame}")?;
+        }
+        if let Some(ref value) = self.value {
+            write!(f, ", found `{value}`")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.inner)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(inner: serde_json::Error) -> Self {
+        Self {
+            path: String::new(),
+            frame: None,
+            value: None,
+            inner,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        serde_json::Error::io(error).into()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+fn format_path(segments: &[Segment]) -> String {
+    segments.iter().fold(String::new(), |mut path, segment| {
+        match segment {
+            Segment::Key(key) if path.is_empty() => path.push_str(key),
+            Segment::Key(key) => write!(path, ".{key}").unwrap(),
+            Segment::Index(index) => write!(path, "[{index}]").unwrap(),
+        }
+        path
+    })
+}
+
+/// Converts the path reported by serde, stopping where serde lost track of the location (as
+/// happens when passing through flattened fields)
+fn known_segments(path: &serde_path_to_error::Path) -> Vec<Segment> {
+    path.iter()
+        .map_while(|segment| match segment {
+            serde_path_to_error::Segment::Seq { index } => Some(Segment::Index(*index)),
+            serde_path_to_error::Segment::Map { key } => Some(Segment::Key(key.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Deserializes a value while keeping track of where a failure happens
+fn deserialize_tracked<'de, T, D>(deserializer: D) -> Result<T, (Vec<Segment>, serde_json::Error)>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de, Error = serde_json::Error>,
+{
+    serde_path_to_error::deserialize(deserializer)
+        .map_err(|error| (known_segments(error.path()), error.into_inner()))
+}
+
+/// Parses an event's contents on their own to find where in the contents an error happened,
+/// returning the path relative to the event.
+fn locate_in_event(event: &Value) -> Option<Vec<Segment>> {
+    let data = event.get("data")?;
+    let inner = match event.get("type")?.as_str()? {
+        "full" => deserialize_tracked::<EventFull, _>(data).err(),
+        "targets" => deserialize_tracked::<Targets, _>(data).err(),
+        "keydown" | "keyup" => deserialize_tracked::<KeyEvent, _>(data).err(),
+        "ige" => deserialize_tracked::<InteractionContainer, _>(data).err(),
+        _ => return Some(vec![Segment::Key("type".to_owned())]),
+    };
+
+    inner.map(|(path, _)| {
+        std::iter::once(Segment::Key("data".to_owned()))
+            .chain(path)
+            .collect()
+    })
+}
+
+impl Error {
+    /// Builds an error from one reported by serde, digging through the input again to find the
+    /// offending value and the event containing it.
+    fn locate(slice: &[u8], mut segments: Vec<Segment>, inner: serde_json::Error) -> Self {
+        let Ok(root) = serde_json::from_slice::<Value>(slice) else {
+            return inner.into();
+        };
+
+        let mut node = &root;
+        let mut frame = None;
+        let mut walked = Vec::new();
+        let mut remaining = segments.clone().into_iter();
+
+        loop {
+            let at_event = matches!(
+                walked.as_slice(),
+                [.., Segment::Key(key), Segment::Index(_)] if key == "events"
+            );
+            let next = remaining.next();
+
+            if at_event {
+                frame = node.get("frame").and_then(Value::as_u64).map(|f| f as u32);
+                // serde loses track of the path inside events (since their contents are flattened),
+                // so find the rest of the path by parsing the event again
+                if next.is_none() {
+                    if let Some(rest) = locate_in_event(node) {
+                        segments.extend(rest.iter().cloned());
+                        remaining = rest.into_iter();
+                        continue;
+                    }
+                }
+            }
+
+            let Some(segment) = next else { break };
+            let child = match segment {
+                Segment::Key(ref key) => node.get(key),
+                Segment::Index(index) => node.get(index),
+            };
+            match child {
+                Some(child) => node = child,
+                None => break,
+            }
+            walked.push(segment);
+        }
+
+        let value = serde_json::to_string(node).ok().map(|mut value| {
+            if let Some((cut, _)) = value.char_indices().nth(VALUE_PREVIEW_LENGTH) {
+                value.truncate(cut);
+                value.push_str("...");
+            }
+            value
+        });
+
+        Self {
+            path: format_path(&segments),
+            frame,
+            // the root is never the offending value, it would be the file itself
+            value: (!walked.is_empty()).then_some(value).flatten(),
+            inner,
+        }
+    }
+
+    /// An error which was found at the given path, but not by serde
+    pub(crate) fn at(path: String, frame: Option<u32>, message: impl Display) -> Self {
+        Self {
+            path,
+            frame,
+            value: None,
+            inner: serde::de::Error::custom(message),
+        }
+    }
+}
+
+/// Deserializes a whole file, reporting the location of any failure
+pub(crate) fn from_slice<'a, T: Deserialize<'a>>(slice: &'a [u8]) -> Result<T, Error> {
+    let mut deserializer = serde_json::Deserializer::from_slice(slice);
+    let value = deserialize_tracked(&mut deserializer)
+        .map_err(|(segments, inner)| Error::locate(slice, segments, inner))?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// Deserializes one event of a file, whose location in the file is given by `path`
+pub(crate) fn event_from_str<'a, T: Deserialize<'a>>(
+    raw: &'a str,
+    path: String,
+) -> Result<T, Error> {
+    serde_json::from_str(raw).map_err(|inner| {
+        let event = serde_json::from_str::<Value>(raw).ok();
+        let frame = event
+            .as_ref()
+            .and_then(|event| event.get("frame"))
+            .and_then(Value::as_u64)
+            .map(|frame| frame as u32);
+        let rest = event.as_ref().and_then(locate_in_event).unwrap_or_default();
+        let mut error = Error::locate(raw.as_bytes(), rest, inner);
+        error.path = match error.path.is_empty() {
+            true => path,
+            false => format!("{path}.{}", error.path),
+        };
+        error.frame = frame;
+        error
+    })
+}
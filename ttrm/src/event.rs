@@ -1,14 +1,28 @@
+use std::borrow::Cow;
+
 use serde::{Deserialize, Serialize};
-use serde_json::{Number, Value};
+use serde_json::{Map, Number, Value};
+
+use crate::{owned, Clears, Finesse, Time};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Event<'a> {
     pub frame: u32,
     #[serde(borrow, flatten)]
     pub data: EventData<'a>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl<'a> Event<'a> {
+    /// Copies any data borrowed from the input so that the event can outlive it
+    pub fn into_owned(self) -> Event<'static> {
+        Event {
+            frame: self.frame,
+            data: self.data.into_owned(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "lowercase")]
 #[serde(tag = "type", content = "data")]
 pub enum EventData<'a> {
@@ -17,7 +31,10 @@ pub enum EventData<'a> {
         #[serde(flatten, borrow)]
         data: Box<EventFull<'a>>,
     },
-    Targets {}, // TODO fill in fields
+    Targets {
+        #[serde(flatten, borrow)]
+        data: Targets<'a>,
+    },
     KeyDown {
         #[serde(flatten)]
         key_event: KeyEvent,
@@ -31,28 +48,181 @@ pub enum EventData<'a> {
         #[serde(flatten)]
         event: Box<InteractionContainer>,
     },
-    End {},
+    End {
+        #[serde(flatten, borrow)]
+        data: Box<EventEnd<'a>>,
+    },
+}
+
+impl<'a> EventData<'a> {
+    pub fn into_owned(self) -> EventData<'static> {
+        match self {
+            EventData::Start {} => EventData::Start {},
+            EventData::Full { data } => EventData::Full {
+                data: Box::new(data.into_owned()),
+            },
+            EventData::Targets { data } => EventData::Targets {
+                data: data.into_owned(),
+            },
+            EventData::KeyDown { key_event } => EventData::KeyDown { key_event },
+            EventData::KeyUp { key_event } => EventData::KeyUp { key_event },
+            EventData::InGameEvent { event } => EventData::InGameEvent { event },
+            EventData::End { data } => EventData::End {
+                data: Box::new(data.into_owned()),
+            },
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct EventFull<'a> {
     #[serde(rename = "aggregatestats")]
     pub aggregate_stats: AggregateStats,
-    pub assumptions: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assumptions: Option<Assumptions>,
     pub fire: Number,
     pub game: Game<'a>,
-    #[serde(rename = "gameoverreason")]
-    pub game_over_reason: Option<&'a str>,
+    #[serde(rename = "gameoverreason", borrow)]
+    pub game_over_reason: Option<Cow<'a, str>>,
     pub killer: Killer<'a>,
-    pub options: GameOptions<'a>,
-    pub replay: Value, //TODO: gonna have to see what these structs mean
-    pub source: Value,
-    pub stats: Value,
+    pub options: GameOptions,
+    pub replay: ExportedObject,
+    pub source: ExportedObject,
+    pub stats: Stats,
     pub successful: bool,
-    pub targets: Vec<Value>, // TODO: probably string, but have to check
+    /// The players targeted at the time of this event (see [Targets]). Each is the user id of the
+    /// player, followed by a number of unknown meaning.
+    #[serde(borrow)]
+    pub targets: Vec<Cow<'a, str>>,
+}
+
+impl<'a> EventFull<'a> {
+    pub fn into_owned(self) -> EventFull<'static> {
+        EventFull {
+            aggregate_stats: self.aggregate_stats,
+            assumptions: self.assumptions,
+            fire: self.fire,
+            game: self.game.into_owned(),
+            game_over_reason: self.game_over_reason.map(owned),
+            killer: self.killer.into_owned(),
+            options: self.options,
+            replay: self.replay,
+            source: self.source,
+            stats: self.stats,
+            successful: self.successful,
+            targets: self.targets.into_iter().map(owned).collect(),
+        }
+    }
+}
+
+/// Sent once the game ends, containing the state of the game at the end
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EventEnd<'a> {
+    /// Why the game ended (such as "clear" or "topout"). Seems to always match
+    /// [EventFull::game_over_reason] of the exported state
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<Cow<'a, str>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub export: Option<EventFull<'a>>,
+}
+
+impl<'a> EventEnd<'a> {
+    pub fn into_owned(self) -> EventEnd<'static> {
+        EventEnd {
+            reason: self.reason.map(owned),
+            export: self.export.map(EventFull::into_owned),
+        }
+    }
+}
+
+/// A javascript object (such as the replay recorder or the event source of a game) which tetrio
+/// exports along with the game state. Its methods are exported as keys with null values, so it does
+/// not carry any known information, but it is kept as-is.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ExportedObject {
+    #[serde(flatten)]
+    pub fields: Map<String, Value>,
 }
 
+/// Assumptions the client has made about the game. Only ever seen empty in multiplayer games and
+/// missing in singleplayer games.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Assumptions {
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// In-game statistics as they stood at the time of the event. These are (nearly) the same
+/// statistics which end up in the [crate::EndContext] of a singleplayer replay.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Stats {
+    pub seed: u64,
+    pub lines: u64,
+    pub level_lines: u64,
+    pub level_lines_needed: u64,
+    pub inputs: u64,
+    pub holds: u64,
+    pub time: Time,
+    pub score: u64,
+    #[serde(rename = "zenlevel")]
+    pub zen_level: u64,
+    #[serde(rename = "zenprogress")]
+    pub zen_progress: u64,
+    pub level: u64,
+    pub combo: u64,
+    #[serde(rename = "currentcombopower")]
+    pub combo_power: u64,
+    #[serde(rename = "topcombo")]
+    pub top_combo: u64,
+    pub btb: u64,
+    #[serde(rename = "topbtb")]
+    pub top_b2b: u64,
+    /// Missing from multiplayer replays
+    #[serde(
+        rename = "currentbtbchainpower",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub current_b2b_power: Option<u64>,
+    pub tspins: u64,
+    #[serde(rename = "piecesplaced")]
+    pub pieces_placed: u64,
+    pub clears: Clears,
+    pub garbage: crate::Garbage,
+    pub kills: u64,
+    pub finesse: Finesse,
+    /// Any statistics not (yet) known to this crate
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Sent whenever the set of players targeted by the owner of the replay changes (in multiplayer
+/// games, this is who the garbage sent by this player will go to).
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename = "targets")]
+pub struct Targets<'a> {
+    /// Identifier of the targeting change. Unlike interactions, this is not a number
+    #[serde(borrow)]
+    pub id: Cow<'a, str>,
+    /// The frame on which the targets changed. Seems to always match the frame of the event
+    /// containing it
+    pub frame: u32,
+    /// The user ids (see [crate::User::user_id]) of every player being targeted
+    #[serde(rename = "data", borrow)]
+    pub targets: Vec<Cow<'a, str>>,
+}
+
+impl<'a> Targets<'a> {
+    pub fn into_owned(self) -> Targets<'static> {
+        Targets {
+            id: owned(self.id),
+            frame: self.frame,
+            targets: self.targets.into_iter().map(owned).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename = "ige")]
 pub struct InteractionContainer {
     pub id: Number,
     pub frame: u32,
@@ -84,6 +254,7 @@ pub enum InteractionData {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename = "garbage")]
 pub struct Garbage {
     pub amt: u16,
     pub x: Number,
@@ -95,10 +266,11 @@ pub struct Garbage {
 pub struct KeyEvent {
     pub key: Key,
     pub subframe: Number,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub hoisted: Option<bool>, //TODO: Figure out what this means
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Key {
     #[serde(rename = "hold")]
     Hold,
@@ -118,58 +290,81 @@ pub enum Key {
     HardDrop,
 }
 
+/// The options a game was played with. Tetrio adds options to this every so often, so any options
+/// which are not known by this crate are collected into [GameOptions::extra] (see
+/// [crate::ParseMode] for rejecting these instead).
+///
+/// Unlike the rest of an event, the options own their strings rather than borrowing them from the
+/// input. Most string options are parsed into enums which keep unknown values as owned strings
+/// anyway, and the few left (skins and labels) are not worth giving every option a lifetime and
+/// copying each of them in [EventFull::into_owned].
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(deny_unknown_fields)]
-pub struct GameOptions<'a> {
+pub struct GameOptions {
     #[serde(rename = "allow180")]
     pub allow_180: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub allow_harddrop: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub are: Option<Number>,
-    #[serde(rename = "b2bchaining")]
+    #[serde(rename = "b2bchaining", skip_serializing_if = "Option::is_none")]
     pub b2b_chaining: Option<bool>,
-    #[serde(rename = "bagtype")]
-    pub bag_type: Option<&'a str>, // change to enum
+    #[serde(rename = "bagtype", skip_serializing_if = "Option::is_none")]
+    pub bag_type: Option<BagType>,
     #[serde(rename = "boardbuffer")]
     pub board_buffer: Number,
     #[serde(rename = "boardheight")]
     pub board_height: Number,
     #[serde(rename = "boardskin")]
-    pub board_skin: &'a str,
+    pub board_skin: String,
     #[serde(rename = "boardwidth")]
     pub board_width: Number,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub clutch: Option<bool>,
     pub countdown: bool,
     /// How many counts before GO (for example, countdown_count = 3 means 3, 2, 1, GO, and game
     /// starts immediately on GO). Seems to only be present in multiplayer matches, presumably
     /// because in tetra league, the first match has five counts while the rest have three.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub countdown_count: Option<Number>,
     /// the amount of time between two counts measured in milliseconds
     pub countdown_interval: Number,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub display_fire: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub display_hold: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub display_next: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub display_shadow: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub display_username: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub display_replay: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub forfeit_time: Option<Number>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub display_progress: Option<bool>,
-    #[serde(rename = "fullinterval")]
+    #[serde(rename = "fullinterval", skip_serializing_if = "Option::is_none")]
     pub full_interval: Option<Number>,
-    #[serde(rename = "fulloffset")]
+    #[serde(rename = "fulloffset", skip_serializing_if = "Option::is_none")]
     pub full_offset: Option<Number>,
-    #[serde(rename = "g")]
+    #[serde(
+        rename = "g",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::js::option_number"
+    )]
     pub gravity: Option<f32>,
     #[serde(rename = "garbagecap")]
     pub garbage_cap: u16,
-    #[serde(rename = "garbagecapincrease")]
+    #[serde(rename = "garbagecapincrease", skip_serializing_if = "Option::is_none")]
     pub garbage_cap_increase: Option<Number>,
-    #[serde(rename = "garbagecapmax")]
+    #[serde(rename = "garbagecapmax", skip_serializing_if = "Option::is_none")]
     pub garbage_cap_max: Option<Number>,
-    #[serde(rename = "garbageincrease")]
+    #[serde(rename = "garbageincrease", skip_serializing_if = "Option::is_none")]
     pub garbage_increase: Option<Number>,
-    #[serde(rename = "garbagemargin")]
+    #[serde(rename = "garbagemargin", skip_serializing_if = "Option::is_none")]
     pub garbage_margin: Option<Number>,
-    #[serde(rename = "garbagemultiplier")]
+    #[serde(rename = "garbagemultiplier", skip_serializing_if = "Option::is_none")]
     pub garbage_multiplier: Option<Number>,
     /// After garbage is acknowledged by a client, there is a delay before it takes effect. After
     /// garbage takes effect, it will be applied to the board on the next hard drop. This quantity
@@ -177,46 +372,79 @@ pub struct GameOptions<'a> {
     #[serde(rename = "garbagespeed")]
     pub garbage_speed: u32,
     #[serde(rename = "ghostskin")]
-    pub ghost_skin: &'a str,
-    #[serde(rename = "gbase")]
+    pub ghost_skin: String,
+    #[serde(
+        rename = "gbase",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::js::option_number"
+    )]
     pub gravity_base: Option<f32>,
-    #[serde(rename = "gspeed")]
+    #[serde(rename = "gspeed", skip_serializing_if = "Option::is_none")]
     pub gravity_speed: Option<Number>,
-    #[serde(rename = "gincrease")]
+    #[serde(
+        rename = "gincrease",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::js::option_number"
+    )]
     pub gravity_increase: Option<f32>,
-    #[serde(rename = "gmargin")]
+    #[serde(rename = "gmargin", skip_serializing_if = "Option::is_none")]
     pub gravity_margin: Option<Number>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub handling: Option<Handling>,
-    #[serde(rename = "hasgarbage")]
+    #[serde(rename = "hasgarbage", skip_serializing_if = "Option::is_none")]
     pub has_garbage: Option<bool>,
-    #[serde(rename = "infinitemovement")]
+    #[serde(rename = "infinitemovement", skip_serializing_if = "Option::is_none")]
     pub infinite_movement: Option<bool>,
-    pub kickset: &'a str,
-    #[serde(rename = "latencypreference")]
-    pub latency_preference: Option<&'a str>, //TODO: Probably has to do with passthrough, but should check
+    pub kickset: KickSet,
+    #[serde(rename = "latencypreference", skip_serializing_if = "Option::is_none")]
+    pub latency_preference: Option<LatencyPreference>, //TODO: Probably has to do with passthrough, but should check
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub lineclear_are: Option<Number>,
-    #[serde(rename = "lockresets")]
+    #[serde(rename = "lockresets", skip_serializing_if = "Option::is_none")]
     pub lock_resets: Option<Number>, // TODO should default to 16, but could depend on the gamemode
-    #[serde(rename = "locktime")]
+    #[serde(rename = "locktime", skip_serializing_if = "Option::is_none")]
     pub lock_time: Option<u64>, // TODO should default to 30, but could depend on the gamemode
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub manual_allowed: Option<bool>,
     #[serde(rename = "minoskin")]
-    pub tetromino_skin: TetrominoSkin<'a>,
-    pub mission: Option<&'a str>,
-    pub mission_type: Option<&'a str>,
-    #[serde(rename = "neverstopbgm")]
+    pub tetromino_skin: TetrominoSkin,
+    #[serde(
+        default,
+        with = "crate::js::nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub mission: Option<Option<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mission_type: Option<String>,
+    #[serde(rename = "neverstopbgm", skip_serializing_if = "Option::is_none")]
     pub loop_bgm: Option<bool>,
-    #[serde(rename = "bgmnoreset")]
+    #[serde(rename = "bgmnoreset", skip_serializing_if = "Option::is_none")]
     pub bgm_no_reset: Option<bool>, // TODO compare with neverstopbgm (could be the same or opposite thing)
     #[serde(rename = "nextcount")]
     pub next_count: Number,
-    pub objective: Value, // TODO: confirm -- this probably refers to lines cleared to finish in singleplayer games and such
-    #[serde(rename = "onfail")]
-    pub on_fail: Option<Value>, // TODO: Find the types of these
-    #[serde(rename = "onfinish")]
-    pub on_finish: Option<Value>,
-    #[serde(rename = "oninteraction")]
-    pub on_interaction: Option<Value>,
+    pub objective: Objective,
+    #[serde(
+        rename = "onfail",
+        default,
+        with = "crate::js::nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub on_fail: Option<Option<Hook>>,
+    #[serde(
+        rename = "onfinish",
+        default,
+        with = "crate::js::nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub on_finish: Option<Option<Hook>>,
+    #[serde(
+        rename = "oninteraction",
+        default,
+        with = "crate::js::nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub on_interaction: Option<Option<Hook>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub passthrough: Option<bool>,
     pub physical: bool,
     #[serde(rename = "precountdown")]
@@ -224,94 +452,322 @@ pub struct GameOptions<'a> {
     #[serde(rename = "prestart")]
     pub pre_start: Number,
     // TODO check if room handling can be grouped together
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub room_handling: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub room_handling_arr: Option<Number>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub room_handling_das: Option<Number>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub room_handling_sdf: Option<Number>,
     pub seed: u64,
     pub seed_random: bool,
-    pub slot_bar1: Option<&'a str>,
-    pub slot_bar2: Option<&'a str>,
-    pub slot_counter1: Option<&'a str>,
-    pub slot_counter2: Option<&'a str>,
-    pub slot_counter3: Option<&'a str>,
-    pub slot_counter4: Option<&'a str>,
-    pub slot_counter5: Option<&'a str>,
-    #[serde(rename = "spinbonuses")]
-    pub spin_bonuses: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot_bar1: Option<SlotBar>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot_bar2: Option<SlotBar>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot_counter1: Option<SlotCounter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot_counter2: Option<SlotCounter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot_counter3: Option<SlotCounter>,
+    #[serde(
+        default,
+        with = "crate::js::nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub slot_counter4: Option<Option<SlotCounter>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot_counter5: Option<SlotCounter>,
+    #[serde(rename = "spinbonuses", skip_serializing_if = "Option::is_none")]
+    pub spin_bonuses: Option<SpinBonuses>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub stock: Option<Number>,
-    pub username: Option<&'a str>,
-    pub version: Number, // TODO Use this!!!
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// The version of the set of options. The options modeled by this struct are the ones found in
+    /// version [crate::KNOWN_OPTIONS_VERSION]
+    pub version: u32,
     #[serde(rename = "zoominto")]
-    pub zoom_into: &'a str,
-    #[serde(rename = "anchorseed")]
+    pub zoom_into: ZoomInto,
+    #[serde(rename = "anchorseed", skip_serializing_if = "Option::is_none")]
     pub anchor_seed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub can_retry: Option<bool>,
     /// Option on forty-line games (maybe blitz as well) to indicate whether or not player used pro
     /// mode (turns on indicators for time left in blitz and lines left to clear in forty-line)
-    #[serde(rename = "pro")]
+    #[serde(rename = "pro", skip_serializing_if = "Option::is_none")]
     pub pro_mode: Option<bool>,
     /// Option on forty-line and blitz games to indicate if the player has stride mode turned on.
     /// This option allows the player to reset the game with less effort, having the effect of a
     /// quicker countdown to open the game
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub stride: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub no_szo: Option<bool>,
-    #[serde(rename = "combotable")]
-    pub combo_table: Option<&'a str>,
-    #[serde(rename = "garbageblocking")]
-    pub garbage_blocking: Option<&'a str>,
+    #[serde(rename = "combotable", skip_serializing_if = "Option::is_none")]
+    pub combo_table: Option<ComboTable>,
+    #[serde(rename = "garbageblocking", skip_serializing_if = "Option::is_none")]
+    pub garbage_blocking: Option<GarbageBlocking>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub levels: Option<bool>,
-    #[serde(rename = "masterlevels")]
+    #[serde(rename = "masterlevels", skip_serializing_if = "Option::is_none")]
     pub master_levels: Option<bool>,
-    #[serde(rename = "startinglevel")]
+    #[serde(rename = "startinglevel", skip_serializing_if = "Option::is_none")]
     pub starting_level: Option<Number>,
-    #[serde(rename = "levelspeed")]
+    #[serde(
+        rename = "levelspeed",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::js::option_number"
+    )]
     pub level_speed: Option<f32>,
-    #[serde(rename = "levelstatic")]
+    #[serde(rename = "levelstatic", skip_serializing_if = "Option::is_none")]
     pub level_static: Option<bool>,
-    #[serde(rename = "levelstaticspeed")]
+    #[serde(rename = "levelstaticspeed", skip_serializing_if = "Option::is_none")]
     pub level_static_speed: Option<Number>,
-    #[serde(rename = "x_resulttype")]
-    pub custom_metric: Option<&'a str>,
-    #[serde(rename = "objective_type")]
-    pub custom_objective: Option<&'a str>,
+    #[serde(rename = "x_resulttype", skip_serializing_if = "Option::is_none")]
+    pub custom_metric: Option<String>,
+    #[serde(rename = "objective_type", skip_serializing_if = "Option::is_none")]
+    pub custom_objective: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub objective_count: Option<Number>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub objective_time: Option<Number>,
-    #[serde(rename = "topoutisclear")]
+    #[serde(rename = "topoutisclear", skip_serializing_if = "Option::is_none")]
     pub topout_clear: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub absolute_lines: Option<bool>,
-    pub song: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub song: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub pro_alert: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub pro_retry: Option<bool>,
-    #[serde(rename = "nolockout")]
+    #[serde(rename = "nolockout", skip_serializing_if = "Option::is_none")]
     pub no_lockout: Option<bool>,
-    #[serde(rename = "survivalmode")]
-    pub survival_mode: Option<&'a str>,
+    #[serde(rename = "survivalmode", skip_serializing_if = "Option::is_none")]
+    pub survival_mode: Option<SurvivalMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub survival_messiness: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub survival_cap: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub survival_layer_amt: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub survival_layer_non: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub survival_layer_min: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub survival_timer_itv: Option<u64>,
+    /// Every option which is not known to this crate, keyed by its name
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
+/// The condition on which a game ends successfully
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TetrominoSkin<'a> {
-    i: &'a str,
-    j: &'a str,
-    l: &'a str,
-    o: &'a str,
-    s: &'a str,
-    t: &'a str,
-    z: &'a str,
-    other: &'a str,
+pub struct Objective {
+    #[serde(rename = "type")]
+    pub kind: ObjectiveKind,
+    /// The amount of lines or garbage lines which must be cleared, depending on [Self::kind]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<u64>,
+    /// The time limit of the game, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+string_enum! {
+    pub enum ObjectiveKind {
+        /// The game only ends on a loss (as in multiplayer games)
+        None = "none",
+        /// Clear [Objective::count] lines (such as in forty-line)
+        Lines = "lines",
+        /// Survive for [Objective::time] milliseconds (such as in blitz)
+        Timed = "timed",
+        /// Clear [Objective::count] garbage lines
+        Garbage = "garbage",
+    }
+}
+
+string_enum! {
+    /// How the pieces of the queue are chosen
+    pub enum BagType {
+        /// Each of the seven pieces once, in a random order
+        SevenBag = "7-bag",
+        /// The same as [BagType::SevenBag], as written by older versions of tetrio
+        LegacySevenBag = "7bag",
+        /// Each of the seven pieces twice, in a random order
+        FourteenBag = "14-bag",
+        /// Random pieces, rerolling once if the piece is the same as the last one
+        Classic = "classic",
+        /// Six pieces, made of three of one piece and three of another
+        Pairs = "pairs",
+        /// Completely random pieces
+        TotalMayhem = "total mayhem",
+        /// Each of the seven pieces once, plus one random piece
+        SevenPlusOne = "7+1-bag",
+        /// Each of the seven pieces once, plus two random pieces
+        SevenPlusTwo = "7+2-bag",
+        SevenPlusX = "7+x-bag",
+    }
+}
+
+string_enum! {
+    /// The kick table used when rotating pieces
+    pub enum KickSet {
+        /// Tetrio's modification of SRS, with symmetric I kicks and 180 kicks
+        SrsPlus = "SRS+",
+        Srs = "SRS",
+        SrsX = "SRS-X",
+        TetraX = "TETRA-X",
+        /// Nintendo rotation system
+        Nrs = "NRS",
+        /// Arika rotation system
+        Ars = "ARS",
+        Asc = "ASC",
+        /// Pieces only rotate in place
+        None = "none",
+    }
+}
+
+string_enum! {
+    /// Which spins are rewarded when clearing lines
+    pub enum SpinBonuses {
+        TSpins = "T-spins",
+        /// Immobile spins of any piece
+        All = "all",
+        /// Immobile spins of any piece, with spins other than T-spins counted as minis
+        AllMini = "all-mini",
+        AllPlus = "all+",
+        AllMiniPlus = "all-mini+",
+        MiniOnly = "mini-only",
+        Handheld = "handheld",
+        /// Any rotation which is followed by a line clear
+        Stupid = "stupid",
+        None = "none",
+    }
+}
+
+string_enum! {
+    /// How combos add to attack
+    pub enum ComboTable {
+        None = "none",
+        ClassicGuideline = "classic guideline",
+        ModernGuideline = "modern guideline",
+        /// Attack is multiplied by the combo, as in tetra league
+        Multiplier = "multiplier",
+    }
+}
+
+string_enum! {
+    /// Whether clearing lines prevents incoming garbage from being added to the board
+    pub enum GarbageBlocking {
+        /// Garbage is not added while a combo is going
+        ComboBlocking = "combo blocking",
+        LimitedBlocking = "limited blocking",
+        /// Garbage is always added
+        None = "none",
+    }
+}
+
+string_enum! {
+    /// How garbage is sent to a player in a custom game without other players
+    pub enum SurvivalMode {
+        None = "none",
+        /// Garbage is sent at regular intervals (see [GameOptions::survival_timer_itv])
+        Timer = "timer",
+    }
+}
+
+string_enum! {
+    pub enum LatencyPreference {
+        Low = "low",
+        Balanced = "balanced",
+        High = "high",
+    }
+}
+
+string_enum! {
+    /// How the camera moves to the board at the start of a game
+    pub enum ZoomInto {
+        Slow = "slow",
+        Fast = "fast",
+        Cinematic = "cinematic",
+    }
+}
+
+string_enum! {
+    /// The statistic shown in a bar next to the board
+    pub enum SlotBar {
+        /// Incoming garbage
+        Impending = "impending",
+        /// Progress towards the objective
+        Progress = "progress",
+    }
+}
+
+string_enum! {
+    /// The statistic shown in a counter next to the board
+    pub enum SlotCounter {
+        Empty = "---empty---",
+        /// Time remaining
+        Timer = "timer",
+        /// Time elapsed
+        Stopwatch = "stopwatch",
+        Lines = "lines",
+        Attack = "attack",
+        Level = "level",
+        Pieces = "pieces",
+        Keys = "keys",
+        Finesse = "finesse",
+        /// Finesse faults
+        FinesseFaults = "finesse_l",
+        Score = "score",
+        /// Versus score
+        Vs = "vs",
+    }
+}
+
+/// An action taken by the game when some condition is met (used by custom games). Only ever seen
+/// as null, so nothing is known about its contents.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Hook {
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The skin of each piece, owned for the same reasons as the rest of [GameOptions]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TetrominoSkin {
+    i: String,
+    j: String,
+    l: String,
+    o: String,
+    s: String,
+    t: String,
+    z: String,
+    other: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Killer<'a> {
-    pub name: Option<&'a str>,
-    #[serde(rename = "type")]
-    pub kind: &'a str,
+    #[serde(borrow)]
+    pub name: Option<Cow<'a, str>>,
+    #[serde(rename = "type", borrow)]
+    pub kind: Cow<'a, str>,
+}
+
+impl<'a> Killer<'a> {
+    pub fn into_owned(self) -> Killer<'static> {
+        Killer {
+            name: self.name.map(owned),
+            kind: owned(self.kind),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -323,10 +779,11 @@ pub struct AggregateStats {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Game<'a> {
-    pub bag: Vec<&'a str>,
+    #[serde(borrow)]
+    pub bag: Vec<Cow<'a, str>>,
     /// null or string (one of "ljzsoti" or "gb" for garbage)
     #[serde(borrow)]
-    pub board: Vec<Vec<Option<&'a str>>>,
+    pub board: Vec<Vec<Option<Cow<'a, str>>>>,
     pub controlling: Controlling,
     #[serde(rename = "g")]
     pub gravity: Number,
@@ -335,10 +792,38 @@ pub struct Game<'a> {
     pub playing: bool,
 }
 
+impl<'a> Game<'a> {
+    pub fn into_owned(self) -> Game<'static> {
+        Game {
+            bag: self.bag.into_iter().map(owned).collect(),
+            board: self
+                .board
+                .into_iter()
+                .map(|row| row.into_iter().map(|cell| cell.map(owned)).collect())
+                .collect(),
+            controlling: self.controlling,
+            gravity: self.gravity,
+            handling: self.handling,
+            hold: self.hold.into_owned(),
+            playing: self.playing,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Hold<'a> {
     pub locked: bool,
-    pub piece: Option<&'a str>,
+    #[serde(borrow)]
+    pub piece: Option<Cow<'a, str>>,
+}
+
+impl<'a> Hold<'a> {
+    pub fn into_owned(self) -> Hold<'static> {
+        Hold {
+            locked: self.locked,
+            piece: self.piece.map(owned),
+        }
+    }
 }
 
 /// Describes timings which relate to the effect of player controls. All floating-point values
@@ -347,20 +832,24 @@ pub struct Hold<'a> {
 pub struct Handling {
     /// From tetrio: "Automatic Repeat Rate: the speed at which tetrominoes move when holding
     /// down movement keys, measured in frames per movement."
+    #[serde(serialize_with = "crate::js::number")]
     pub arr: f64,
     /// From tetrio: "If enabled, DAS charge is cancelled when you change directions."
     pub cancel: bool,
     /// From tetrio: "Delayed Auto Shift: the time between the initial keypress and the start of
     /// its automatic repeat movement, measured in frames."
+    #[serde(serialize_with = "crate::js::number")]
     pub das: f64,
     /// From tetrio: "DAS Cut Delay: if not 0, any ongoing DAS movement will pause for a set
     /// amount of time after dropping/rotating a piece, measured in frames."
+    #[serde(serialize_with = "crate::js::number")]
     pub dcd: f64,
     /// From tetrio: "If enabled, when a piece locks on its own, the hard drop key becomes
     /// unavailable for a few frame. This prevents accidental hard drops."
     pub safelock: bool,
     /// From tetrio: "Soft Drop Factor: the factor with which soft drops change the gravity
     /// speed."
+    #[serde(serialize_with = "crate::js::number")]
     pub sdf: f64,
 }
 
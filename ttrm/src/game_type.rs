@@ -1,12 +1,15 @@
-use serde::{Deserialize, Serialize};
-
-#[non_exhaustive]
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
-#[serde(rename_all = "lowercase")]
-pub enum GameType {
-    #[serde(rename = "40l")]
-    FortyLine,
-    League,
-    Custom,
-    Blitz,
+string_enum! {
+    /// The mode a game was played in
+    pub enum GameType {
+        FortyLine = "40l",
+        Blitz = "blitz",
+        Zen = "zen",
+        Custom = "custom",
+        /// Tetra league, the ranked multiplayer mode
+        League = "league",
+        /// Quick play, the multiplayer mode where players climb a tower together
+        QuickPlay = "zenith",
+        /// Quick play with the expert modifier
+        ExpertQuickPlay = "zenithex",
+    } else Unknown
 }
@@ -0,0 +1,87 @@
+//! Helpers for writing replays back out the same way TETR.IO (and so javascript) wrote them.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Largest integer which javascript can represent exactly
+const MAX_SAFE_INTEGER: f64 = 9007199254740991.;
+
+/// Floating-point numbers, which javascript writes without a fractional part when they are whole
+pub(crate) trait JsNumber: Copy {
+    fn serialize_js<S: Serializer>(self, serializer: S) -> Result<S::Ok, S::Error>;
+}
+
+impl JsNumber for f64 {
+    fn serialize_js<S: Serializer>(self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.fract() == 0. && self.abs() <= MAX_SAFE_INTEGER {
+            serializer.serialize_i64(self as i64)
+        } else {
+            serializer.serialize_f64(self)
+        }
+    }
+}
+
+impl JsNumber for f32 {
+    fn serialize_js<S: Serializer>(self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.fract() == 0. && (self as f64).abs() <= MAX_SAFE_INTEGER {
+            serializer.serialize_i64(self as i64)
+        } else {
+            // written as an f32 so that the shortest representation of the original is kept
+            serializer.serialize_f32(self)
+        }
+    }
+}
+
+pub(crate) fn number<T: JsNumber, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    value.serialize_js(serializer)
+}
+
+pub(crate) fn option_number<T: JsNumber, S: Serializer>(
+    value: &Option<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => value.serialize_js(serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub(crate) fn numbers<T: JsNumber, S: Serializer>(
+    values: &[T],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(values.iter().map(|value| Js(*value)))
+}
+
+/// Serializes the number inside the way javascript would
+struct Js<T>(T);
+
+impl<T: JsNumber> Serialize for Js<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize_js(serializer)
+    }
+}
+
+/// For values which are sometimes missing and sometimes explicitly null. A missing value is read
+/// as `None`, and a null value as `Some(None)`, so that each is written back the same way.
+pub(crate) mod nullable {
+    use super::*;
+
+    pub(crate) fn serialize<T: Serialize, S: Serializer>(
+        value: &Option<Option<T>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => value.serialize(serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub(crate) fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Option<T>>, D::Error> {
+        Option::deserialize(deserializer).map(Some)
+    }
+}
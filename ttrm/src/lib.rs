@@ -1,17 +1,94 @@
+#[macro_use]
+mod string_enum;
+
+pub mod anonymize;
+pub mod compression;
+mod convert;
+mod edit;
+mod error;
 pub mod event;
 mod game_type;
+mod js;
+mod parse;
+pub mod stream;
 mod ttr;
 mod ttrm;
+mod validate;
+
+use std::{borrow::Cow, io::Read};
 
+pub use convert::*;
+pub use error::Error;
 pub use game_type::*;
-pub use serde_json::Error;
+pub use parse::*;
 pub use ttr::*;
 pub use ttrm::*;
+pub use validate::*;
+
+/// Reads a ttr file, which may be compressed (see [compression]). The replay borrows from the
+/// input, unless it had to be decompressed first.
+pub fn ttr_from_slice(slice: &[u8], mode: ParseMode) -> Result<Ttr<'_>, Error> {
+    match compression::decompress(slice)? {
+        Cow::Borrowed(json) => parse_ttr(json, mode),
+        Cow::Owned(json) => parse_ttr(&json, mode).map(Ttr::into_owned),
+    }
+}
+
+/// Reads a ttrm file, which may be compressed (see [compression]). The replay borrows from the
+/// input, unless it had to be decompressed first.
+pub fn ttrm_from_slice(slice: &[u8], mode: ParseMode) -> Result<Ttrm<'_>, Error> {
+    match compression::decompress(slice)? {
+        Cow::Borrowed(json) => parse_ttrm(json, mode),
+        Cow::Owned(json) => parse_ttrm(&json, mode).map(Ttrm::into_owned),
+    }
+}
+
+fn parse_ttr(json: &[u8], mode: ParseMode) -> Result<Ttr<'_>, Error> {
+    let ttr: Ttr = error::from_slice(json)?;
+    mode.check([("data".to_owned(), &ttr.data)])?;
+    Ok(ttr)
+}
+
+fn parse_ttrm(json: &[u8], mode: ParseMode) -> Result<Ttrm<'_>, Error> {
+    let ttrm: Ttrm = error::from_slice(json)?;
+    mode.check(ttrm.data.iter().enumerate().flat_map(|(round, set)| {
+        set.replays
+            .iter()
+            .enumerate()
+            .map(move |(player, replay)| (format!("data[{round}].replays[{player}]"), replay))
+    }))?;
+    Ok(ttrm)
+}
+
+/// Reads an entire (possibly compressed) ttr file from the reader, producing a replay that does not
+/// borrow from the input
+pub fn ttr_from_reader(mut reader: impl Read, mode: ParseMode) -> Result<Ttr<'static>, Error> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+    ttr_from_slice(&buf, mode).map(Ttr::into_owned)
+}
+
+/// Reads an entire (possibly compressed) ttrm file from the reader, producing a replay that does
+/// not borrow from the input
+pub fn ttrm_from_reader(mut reader: impl Read, mode: ParseMode) -> Result<Ttrm<'static>, Error> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+    ttrm_from_slice(&buf, mode).map(Ttrm::into_owned)
+}
+
+/// Writes a ttr file. Writing a replay which was read with [ttr_from_slice] gives the same json
+/// as the original file (up to formatting and the order of keys).
+pub fn ttr_to_vec(ttr: &Ttr) -> Result<Vec<u8>, Error> {
+    Ok(serde_json::to_vec(ttr)?)
+}
 
-pub fn ttr_from_slice(slice: &[u8]) -> Result<Ttr, serde_json::Error> {
-    serde_json::from_slice(slice)
+/// Writes a ttrm file. Writing a replay which was read with [ttrm_from_slice] gives the same json
+/// as the original file (up to formatting and the order of keys).
+pub fn ttrm_to_vec(ttrm: &Ttrm) -> Result<Vec<u8>, Error> {
+    Ok(serde_json::to_vec(ttrm)?)
 }
 
-pub fn ttrm_from_slice(slice: &[u8]) -> Result<Ttrm, serde_json::Error> {
-    serde_json::from_slice(slice)
+/// Detaches a string from the input it was parsed from
+fn owned(string: Cow<str>) -> Cow<'static, str> {
+    Cow::Owned(string.into_owned())
 }
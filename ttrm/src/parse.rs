@@ -0,0 +1,126 @@
+use std::fmt::Display;
+
+use crate::{
+    event::{EventData, GameOptions},
+    Error, Replay, Ttr, Ttrm,
+};
+
+/// The version of [GameOptions] which this crate models. Replays with other versions may contain
+/// options that are not understood, or miss some which are expected.
+pub const KNOWN_OPTIONS_VERSION: u32 = 15;
+
+/// Determines how options which are not known to this crate are treated while parsing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    /// Fail to parse any replay with unknown options. Useful for detecting changes in the format.
+    Strict,
+    /// Collect unknown options into [GameOptions::extra]. These can be listed afterward with
+    /// `unknown_options` (for example, [Ttr::unknown_options]).
+    #[default]
+    Lenient,
+}
+
+/// A game option which was not recognized while parsing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownOption {
+    /// The frame of the event which contained the options
+    pub frame: u32,
+    /// The version of the options which contained this option
+    pub version: u32,
+    pub key: String,
+}
+
+impl Display for UnknownOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "unknown game option `{}` on frame {} (options version {}, expected version {})",
+            self.key, self.frame, self.version, KNOWN_OPTIONS_VERSION
+        )
+    }
+}
+
+impl ParseMode {
+    /// Checks the unknown options found while parsing against this mode, failing on the first
+    /// unknown option in strict mode. Options are given along with the path to the replay they
+    /// were found in.
+    pub(crate) fn check<'a>(
+        self,
+        replays: impl IntoIterator<Item = (String, &'a Replay<'a>)>,
+    ) -> Result<(), Error> {
+        if self == ParseMode::Lenient {
+            return Ok(());
+        }
+
+        replays
+            .into_iter()
+            .flat_map(|(path, replay)| {
+                replay
+                    .events
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(ix, event)| match event.data {
+                        EventData::Full { ref data } => Some((
+                            format!("{path}.events[{ix}].data.options"),
+                            unknown_in_options(event.frame, &data.options),
+                        )),
+                        _ => None,
+                    })
+            })
+            .flat_map(|(path, unknown)| unknown.map(move |option| (path.clone(), option)))
+            .next()
+            .map_or(Ok(()), |(path, option)| {
+                Err(Error::at(
+                    format!("{path}.{}", option.key),
+                    Some(option.frame),
+                    option,
+                ))
+            })
+    }
+}
+
+fn unknown_in_options<'a>(
+    frame: u32,
+    options: &'a GameOptions,
+) -> impl Iterator<Item = UnknownOption> + 'a {
+    let version = options.version;
+    options.extra.keys().map(move |key| UnknownOption {
+        frame,
+        version,
+        key: key.clone(),
+    })
+}
+
+impl<'a> Replay<'a> {
+    /// Lists every option that was not recognized in the full events of this replay
+    pub fn unknown_options(&self) -> Vec<UnknownOption> {
+        self.events
+            .iter()
+            .flat_map(|event| match event.data {
+                EventData::Full { ref data } => {
+                    Some(unknown_in_options(event.frame, &data.options))
+                }
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+}
+
+impl<'a> Ttr<'a> {
+    /// Lists every option that was not recognized in this replay
+    pub fn unknown_options(&self) -> Vec<UnknownOption> {
+        self.data.unknown_options()
+    }
+}
+
+impl<'a> Ttrm<'a> {
+    /// Lists every option that was not recognized in any of the replays in this file
+    pub fn unknown_options(&self) -> Vec<UnknownOption> {
+        self.data
+            .iter()
+            .flat_map(|set| set.replays.iter())
+            .flat_map(Replay::unknown_options)
+            .collect()
+    }
+}
@@ -0,0 +1,333 @@
+//! Reading the events of a ttrm file one at a time.
+//!
+//! Parsing a whole [crate::Ttrm] materializes every event of every round up front, which is
+//! expensive for long matches. Instead, [ttrm_events] reads the file as it goes, walking through
+//! the structure of the file and only holding on to the event it is currently parsing.
+//!
+//! Since the game type is needed before any replay can be played back, it must come before the
+//! replays in the file. This is the case for files saved by tetrio and files written by this crate.
+
+use std::io::{BufReader, Bytes, Read};
+
+use crate::{compression::Compression, error, event::Event, Error, GameType};
+
+/// An event, along with the replay it belongs to
+#[derive(Debug)]
+pub struct StreamedEvent<'a> {
+    /// The index of the round (see [crate::Ttrm::data]) the event was found in
+    pub round: usize,
+    /// The index of the replay (see [crate::ReplaySet::replays]) the event was found in
+    pub player: usize,
+    pub event: Event<'a>,
+}
+
+/// Where the stream is in the file, from the list of rounds down to the list of events of a replay
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Position {
+    Rounds,
+    Round,
+    Replays,
+    Replay,
+    Events,
+}
+
+/// Reads through json byte by byte, copying or skipping over whole values
+struct Scanner<R: Read> {
+    bytes: Bytes<BufReader<R>>,
+    peeked: Option<u8>,
+}
+
+impl<R: Read> Scanner<R> {
+    fn peek(&mut self) -> Result<Option<u8>, Error> {
+        if self.peeked.is_none() {
+            self.peeked = self.bytes.next().transpose()?;
+        }
+        Ok(self.peeked)
+    }
+
+    fn next(&mut self, path: &str) -> Result<u8, Error> {
+        let byte = self.peek()?;
+        self.peeked = None;
+        byte.ok_or_else(|| Error::at(path.to_owned(), None, "unexpected end of file"))
+    }
+
+    /// Skips whitespace, giving the byte after it without reading past it
+    fn skip_whitespace(&mut self) -> Result<Option<u8>, Error> {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek()? {
+            self.peeked = None;
+        }
+        self.peek()
+    }
+
+    fn expect(&mut self, expected: u8, path: &str) -> Result<(), Error> {
+        self.skip_whitespace()?;
+        match self.next(path)? {
+            byte if byte == expected => Ok(()),
+            byte => Err(Error::at(
+                path.to_owned(),
+                None,
+                format!("expected `{}`, found `{}`", expected as char, byte as char),
+            )),
+        }
+    }
+
+    /// Moves on to the next element of an array, giving false once the array ends
+    fn next_element(&mut self, first: bool, path: &str) -> Result<bool, Error> {
+        if self.skip_whitespace()? == Some(b']') {
+            self.peeked = None;
+            return Ok(false);
+        }
+        if !first {
+            self.expect(b',', path)?;
+        }
+        Ok(true)
+    }
+
+    /// Moves on to the next entry of an object and reads its key, giving None once the object ends
+    fn next_key(&mut self, first: bool, path: &str) -> Result<Option<String>, Error> {
+        if self.skip_whitespace()? == Some(b'}') {
+            self.peeked = None;
+            return Ok(None);
+        }
+        if !first {
+            self.expect(b',', path)?;
+        }
+        self.skip_whitespace()?;
+        let mut key = Vec::new();
+        self.value(Some(&mut key), path)?;
+        self.expect(b':', path)?;
+        Ok(Some(serde_json::from_slice(&key)?))
+    }
+
+    /// Reads a whole value, copying it into `out` if given
+    fn value(&mut self, mut out: Option<&mut Vec<u8>>, path: &str) -> Result<(), Error> {
+        let mut push = |byte| {
+            if let Some(ref mut out) = out {
+                out.push(byte);
+            }
+        };
+
+        self.skip_whitespace()?;
+        let mut depth = 0usize;
+        let mut in_string = false;
+        loop {
+            let byte = self.next(path)?;
+            push(byte);
+            match byte {
+                b'\\' if in_string => push(self.next(path)?),
+                b'"' => in_string = !in_string,
+                _ if in_string => (),
+                b'{' | b'[' => depth += 1,
+                b'}' | b']' => depth = depth.saturating_sub(1),
+                _ => (),
+            }
+
+            // numbers and literals end wherever the next byte does not belong to them
+            let scalar_ended = !matches!(
+                self.peek()?,
+                Some(b'0'..=b'9' | b'a'..=b'z' | b'A'..=b'Z' | b'+' | b'-' | b'.')
+            );
+            if !in_string
+                && depth == 0
+                && (byte == b'"' || matches!(byte, b'}' | b']') || scalar_ended)
+            {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Iterates through the events of a ttrm file, parsing each one as it is reached. Events are given
+/// in the order they appear in the file -- round by round, then player by player.
+///
+/// Events which cannot be parsed are given as errors without stopping the stream, but the stream
+/// ends after the first error in the structure of the file itself.
+pub struct EventStream<R: Read> {
+    scanner: Scanner<R>,
+    game_type: GameType,
+    position: Position,
+    /// Whether the array or object the stream is in has not had any elements yet
+    first: bool,
+    round: usize,
+    player: usize,
+    event: usize,
+    done: bool,
+}
+
+impl<R: Read> EventStream<R> {
+    pub fn game_type(&self) -> &GameType {
+        &self.game_type
+    }
+
+    fn path(&self) -> String {
+        match self.position {
+            Position::Rounds => "data".to_owned(),
+            Position::Round => format!("data[{}]", self.round),
+            Position::Replays => format!("data[{}].replays", self.round),
+            Position::Replay => format!("data[{}].replays[{}]", self.round, self.player),
+            Position::Events => format!(
+                "data[{}].replays[{}].events[{}]",
+                self.round, self.player, self.event
+            ),
+        }
+    }
+
+    /// Reads up to the next event, giving its json
+    fn next_event(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        loop {
+            let path = self.path();
+            let first = std::mem::replace(&mut self.first, true);
+            match self.position {
+                Position::Rounds => {
+                    if !self.scanner.next_element(first, &path)? {
+                        return Ok(None);
+                    }
+                    self.scanner.expect(b'{', &path)?;
+                    self.position = Position::Round;
+                }
+                Position::Round => match self.scanner.next_key(first, &path)?.as_deref() {
+                    None => {
+                        self.round += 1;
+                        (self.position, self.first) = (Position::Rounds, false);
+                    }
+                    Some("replays") => {
+                        self.scanner.expect(b'[', &path)?;
+                        self.player = 0;
+                        self.position = Position::Replays;
+                    }
+                    Some(_) => {
+                        self.scanner.value(None, &path)?;
+                        self.first = false;
+                    }
+                },
+                Position::Replays => {
+                    if !self.scanner.next_element(first, &path)? {
+                        (self.position, self.first) = (Position::Round, false);
+                        continue;
+                    }
+                    self.scanner.expect(b'{', &path)?;
+                    self.position = Position::Replay;
+                }
+                Position::Replay => match self.scanner.next_key(first, &path)?.as_deref() {
+                    None => {
+                        self.player += 1;
+                        (self.position, self.first) = (Position::Replays, false);
+                    }
+                    Some("events") => {
+                        self.scanner.expect(b'[', &path)?;
+                        self.event = 0;
+                        self.position = Position::Events;
+                    }
+                    Some(_) => {
+                        self.scanner.value(None, &path)?;
+                        self.first = false;
+                    }
+                },
+                Position::Events => {
+                    if !self.scanner.next_element(first, &path)? {
+                        (self.position, self.first) = (Position::Replay, false);
+                        continue;
+                    }
+                    let mut event = Vec::new();
+                    self.scanner.value(Some(&mut event), &path)?;
+                    self.first = false;
+                    return Ok(Some(event));
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for EventStream<R> {
+    type Item = Result<StreamedEvent<'static>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let raw = match self.next_event() {
+            Ok(Some(raw)) => raw,
+            Ok(None) => {
+                self.done = true;
+                return None;
+            }
+            Err(error) => {
+                self.done = true;
+                return Some(Err(error));
+            }
+        };
+
+        let path = self.path();
+        let (round, player) = (self.round, self.player);
+        self.event += 1;
+        let event = String::from_utf8(raw)
+            .map_err(|_| Error::at(path.clone(), None, "the event is not valid utf-8"))
+            .and_then(|raw| error::event_from_str::<Event>(&raw, path).map(Event::into_owned));
+        Some(event.map(|event| StreamedEvent {
+            round,
+            player,
+            event,
+        }))
+    }
+}
+
+/// Prepares to read the events of a ttrm file one at a time. The file is read up to its first
+/// round, which must be preceded by its game type.
+///
+/// A compressed file must be decompressed as it is read (for example with
+/// [flate2::read::MultiGzDecoder] for gzip) before it is given to this.
+pub fn ttrm_events<R: Read>(reader: R) -> Result<EventStream<R>, Error> {
+    let mut reader = BufReader::new(reader);
+    let compression = Compression::detect(std::io::BufRead::fill_buf(&mut reader)?);
+    if compression != Compression::None {
+        return Err(Error::at(
+            String::new(),
+            None,
+            format!("the file is compressed ({compression:?}), and must be decompressed first"),
+        ));
+    }
+
+    let mut scanner = Scanner {
+        bytes: reader.bytes(),
+        peeked: None,
+    };
+    scanner.expect(b'{', "")?;
+    let mut game_type = None;
+    let mut first = true;
+    loop {
+        let key = scanner.next_key(first, "")?;
+        first = false;
+        match key.as_deref() {
+            Some("gametype") => {
+                let mut raw = Vec::new();
+                scanner.value(Some(&mut raw), "gametype")?;
+                game_type = Some(serde_json::from_slice(&raw).map_err(|inner| Error {
+                    path: "gametype".to_owned(),
+                    ..Error::from(inner)
+                })?);
+            }
+            Some("data") => break,
+            Some(key) => scanner.value(None, key)?,
+            None => return Err(Error::at(String::new(), None, "missing field `data`")),
+        }
+    }
+    let game_type = game_type.ok_or_else(|| {
+        Error::at(
+            "data".to_owned(),
+            None,
+            "`gametype` must come before `data`",
+        )
+    })?;
+    scanner.expect(b'[', "data")?;
+
+    Ok(EventStream {
+        scanner,
+        game_type,
+        position: Position::Rounds,
+        first: true,
+        round: 0,
+        player: 0,
+        event: 0,
+        done: false,
+    })
+}
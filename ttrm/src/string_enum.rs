@@ -0,0 +1,86 @@
+/// Declares an enum which is (de)serialized as one of a fixed set of strings. Any string which is
+/// not recognized is kept in an `Other` variant instead of failing, since tetrio is free to add new
+/// values to any of these at any time.
+///
+/// The variant holding unrecognized strings is named `Other` unless another name is given after
+/// the enum, as in `enum Foo { ... } else Unknown`.
+macro_rules! string_enum {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident = $string:literal,)*
+        }
+    ) => {
+        string_enum! {
+            $(#[$meta])*
+            $vis enum $name {
+                $($(#[$variant_meta])* $variant = $string,)*
+            } else Other
+        }
+    };
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident = $string:literal,)*
+        } else $other:ident
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        $vis enum $name {
+            $($(#[$variant_meta])* $variant,)*
+            /// A value which is not (yet) known to this crate
+            $other(String),
+        }
+
+        impl $name {
+            /// The string that tetrio uses to represent this value
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Self::$variant => $string,)*
+                    Self::$other(other) => other,
+                }
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(string: &str) -> Self {
+                match string {
+                    $($string => Self::$variant,)*
+                    other => Self::$other(other.to_owned()),
+                }
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct Visitor;
+
+                impl<'de> serde::de::Visitor<'de> for Visitor {
+                    type Value = $name;
+
+                    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                        f.write_str(concat!("a string describing a ", stringify!($name)))
+                    }
+
+                    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<$name, E> {
+                        Ok(v.into())
+                    }
+                }
+
+                deserializer.deserialize_str(Visitor)
+            }
+        }
+    };
+}
@@ -1,27 +1,58 @@
+use std::borrow::Cow;
+
 use serde::{Deserialize, Serialize};
 use serde_json::Number;
 
-use crate::{GameType, Replay, User};
+use crate::{owned, GameType, Replay, User};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Ttr<'a> {
+    /// Missing from replays which were not uploaded to tetrio (such as custom games)
+    #[serde(rename = "_id", borrow, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Cow<'a, str>>,
+    #[serde(rename = "shortid", borrow, skip_serializing_if = "Option::is_none")]
+    pub short_id: Option<Cow<'a, str>>,
     #[serde(borrow)]
     pub user: User<'a>,
     #[serde(rename = "endcontext")]
     pub end_context: EndContext,
-    #[serde(rename = "ts")]
-    pub timestamp: &'a str,
+    #[serde(rename = "ts", borrow)]
+    pub timestamp: Cow<'a, str>,
     pub data: Replay<'a>,
     #[serde(rename = "gametype")]
     pub game_type: GameType,
-    #[serde(rename = "customtype")]
-    pub custom_type: Option<&'a str>,
+    #[serde(rename = "customtype", borrow, skip_serializing_if = "Option::is_none")]
+    pub custom_type: Option<Cow<'a, str>>,
+    /// Seems to be the time the replay was last modified, in milliseconds since the unix epoch
+    #[serde(rename = "mt", skip_serializing_if = "Option::is_none")]
+    pub modified_time: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+}
+
+impl<'a> Ttr<'a> {
+    /// Copies any data borrowed from the input so that the replay can outlive it
+    pub fn into_owned(self) -> Ttr<'static> {
+        Ttr {
+            id: self.id.map(owned),
+            short_id: self.short_id.map(owned),
+            user: self.user.into_owned(),
+            end_context: self.end_context,
+            timestamp: owned(self.timestamp),
+            data: self.data.into_owned(),
+            game_type: self.game_type,
+            custom_type: self.custom_type.map(owned),
+            modified_time: self.modified_time,
+            verified: self.verified,
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EndContext {
     pub seed: u64,
     pub lines: u64,
+    pub level_lines: u64,
     pub level_lines_needed: u64,
     pub inputs: u64,
     pub holds: u64,
@@ -49,12 +80,16 @@ pub struct EndContext {
     pub garbage: Garbage,
     pub kills: u64,
     pub finesse: Finesse,
-    #[serde(rename = "finalTime")]
+    #[serde(rename = "finalTime", serialize_with = "crate::js::number")]
     pub final_time: f64,
+    /// Only present in replays which were uploaded to tetrio
+    #[serde(rename = "gametype", skip_serializing_if = "Option::is_none")]
+    pub game_type: Option<GameType>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Time {
+    #[serde(serialize_with = "crate::js::number")]
     pub start: f64,
     pub zero: bool,
     pub locked: bool,
@@ -63,7 +98,7 @@ pub struct Time {
     pub frame_offset: Number, // could possibly be u8
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Clears {
     pub singles: u64,
     pub doubles: u64,
@@ -80,7 +115,7 @@ pub struct Clears {
     pub allclear: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Garbage {
     pub sent: u64,
     pub received: u64,
@@ -88,7 +123,7 @@ pub struct Garbage {
     pub cleared: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Finesse {
     pub combo: u64,
     pub faults: u64,
@@ -1,35 +1,145 @@
+use std::borrow::Cow;
+
 use serde::{Deserialize, Serialize};
 use serde_json::Number;
 
-use crate::{event, GameType};
+use crate::{event, owned, GameType};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Ttrm<'a> {
-    #[serde(rename = "_id")]
-    pub id: &'a str,
-    pub back: &'a str,
-    pub data: Vec<ReplaySet<'a>>,
-    #[serde(rename = "forcestyle")]
-    pub force_style: &'a str,
+    #[serde(rename = "_id", borrow)]
+    pub id: Cow<'a, str>,
+    #[serde(borrow)]
+    pub back: Cow<'a, str>,
+    /// Written before [Ttrm::data] so that written files can be streamed (see [crate::stream])
     #[serde(rename = "gametype")]
     pub game_type: GameType,
+    pub data: Vec<ReplaySet<'a>>,
+    /// The results of the match for each player
+    #[serde(rename = "endcontext", borrow)]
+    pub end_context: Vec<PlayerEndContext<'a>>,
+    #[serde(rename = "forcestyle", borrow)]
+    pub force_style: Cow<'a, str>,
     #[serde(rename = "ismulti")]
     pub is_multi: bool,
-    #[serde(rename = "shortid")]
-    pub short_id: &'a str,
-    #[serde(rename = "ts")]
-    pub timestamp: &'a str,
+    #[serde(rename = "shortid", borrow)]
+    pub short_id: Cow<'a, str>,
+    #[serde(rename = "ts", borrow)]
+    pub timestamp: Cow<'a, str>,
     pub verified: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl<'a> Ttrm<'a> {
+    /// Copies any data borrowed from the input so that the replay can outlive it
+    pub fn into_owned(self) -> Ttrm<'static> {
+        Ttrm {
+            id: owned(self.id),
+            back: owned(self.back),
+            data: self.data.into_iter().map(ReplaySet::into_owned).collect(),
+            end_context: self
+                .end_context
+                .into_iter()
+                .map(PlayerEndContext::into_owned)
+                .collect(),
+            force_style: owned(self.force_style),
+            game_type: self.game_type,
+            is_multi: self.is_multi,
+            short_id: owned(self.short_id),
+            timestamp: owned(self.timestamp),
+            verified: self.verified,
+        }
+    }
+}
+
+/// How a single player did over the course of a match
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerEndContext<'a> {
+    /// The position of this player among the players of the match
+    #[serde(rename = "naturalorder")]
+    pub natural_order: u32,
+    #[serde(borrow)]
+    pub user: User<'a>,
+    pub active: bool,
+    /// The number of rounds won
+    pub wins: u32,
+    pub points: Points,
+    /// The number of keys pressed over the whole match
+    pub inputs: u64,
+    /// The number of pieces placed over the whole match
+    #[serde(rename = "piecesplaced")]
+    pub pieces_placed: u64,
+}
+
+impl<'a> PlayerEndContext<'a> {
+    pub fn into_owned(self) -> PlayerEndContext<'static> {
+        PlayerEndContext {
+            natural_order: self.natural_order,
+            user: self.user.into_owned(),
+            active: self.active,
+            wins: self.wins,
+            points: self.points,
+            inputs: self.inputs,
+            pieces_placed: self.pieces_placed,
+        }
+    }
+}
+
+/// The scores of a player over a match, both overall and for each round. The statistics of each
+/// round are listed in the same order as [Ttrm::data].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Points {
+    /// The number of rounds won, the same as [PlayerEndContext::wins]
+    #[serde(rename = "primary")]
+    pub wins: u32,
+    /// Attack per minute, averaged over every round
+    #[serde(rename = "secondary", serialize_with = "crate::js::number")]
+    pub apm: f64,
+    /// Pieces per second, averaged over every round
+    #[serde(rename = "tertiary", serialize_with = "crate::js::number")]
+    pub pps: f64,
+    pub extra: ExtraPoints,
+    #[serde(rename = "secondaryAvgTracking", serialize_with = "crate::js::numbers")]
+    pub apm_per_round: Vec<f64>,
+    #[serde(rename = "tertiaryAvgTracking", serialize_with = "crate::js::numbers")]
+    pub pps_per_round: Vec<f64>,
+    #[serde(rename = "extraAvgTracking")]
+    pub extra_per_round: ExtraPointsPerRound,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtraPoints {
+    /// Versus score, averaged over every round
+    #[serde(serialize_with = "crate::js::number")]
+    pub vs: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtraPointsPerRound {
+    /// Versus score of each round
+    #[serde(
+        rename = "aggregatestats___vsscore",
+        serialize_with = "crate::js::numbers"
+    )]
+    pub vs: Vec<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReplaySet<'a> {
     #[serde(rename = "board", borrow)]
     pub boards: Vec<Board<'a>>,
     pub replays: Vec<Replay<'a>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl<'a> ReplaySet<'a> {
+    pub fn into_owned(self) -> ReplaySet<'static> {
+        ReplaySet {
+            boards: self.boards.into_iter().map(Board::into_owned).collect(),
+            replays: self.replays.into_iter().map(Replay::into_owned).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Board<'a> {
     pub active: bool,
     pub success: bool,
@@ -38,17 +148,51 @@ pub struct Board<'a> {
     pub winning: Number,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl<'a> Board<'a> {
+    pub fn into_owned(self) -> Board<'static> {
+        Board {
+            active: self.active,
+            success: self.success,
+            user: self.user.into_owned(),
+            winning: self.winning,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User<'a> {
-    #[serde(rename = "_id")]
-    pub user_id: &'a str,
-    pub username: &'a str,
+    #[serde(rename = "_id", borrow)]
+    pub user_id: Cow<'a, str>,
+    #[serde(borrow)]
+    pub username: Cow<'a, str>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl<'a> User<'a> {
+    pub fn into_owned(self) -> User<'static> {
+        User {
+            user_id: owned(self.user_id),
+            username: owned(self.username),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Replay<'a> {
     // pub events: Vec<Value>,
     #[serde(borrow)]
     pub events: Vec<event::Event<'a>>,
     pub frames: Number,
 }
+
+impl<'a> Replay<'a> {
+    pub fn into_owned(self) -> Replay<'static> {
+        Replay {
+            events: self
+                .events
+                .into_iter()
+                .map(event::Event::into_owned)
+                .collect(),
+            frames: self.frames,
+        }
+    }
+}
@@ -0,0 +1,229 @@
+//! Checking replays for internal consistency before they are used.
+
+use std::{collections::HashSet, fmt::Display};
+
+use crate::{
+    event::{EventData, InteractionData, Key},
+    Replay,
+};
+
+/// How much a problem with a replay matters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The replay is unusual, but can likely still be reconstructed
+    Warning,
+    /// The replay is malformed, and reconstructing it will likely fail or go wrong
+    Error,
+}
+
+/// A problem found in a replay
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    /// An event came before an event on an earlier frame
+    FrameOutOfOrder { previous: u32 },
+    /// A key was pressed while it was already held down
+    KeyAlreadyDown { key: Key },
+    /// A key was released while it was not held down
+    KeyNotDown { key: Key },
+    /// A key was still held down when the replay ran out of events without ending. Keys held on
+    /// the last frame of a game which ended properly are not reported.
+    KeyNeverReleased { key: Key },
+    /// The subframe of an input was outside of [0, 1)
+    SubframeOutOfRange { subframe: f64 },
+    /// An input came before the full event describing the initial state of the game
+    InputBeforeFull,
+    /// The replay has no full event at all, so the initial state of the game is not known
+    MissingFull,
+    /// An interaction was numbered before a previous interaction of the same kind
+    InteractionOutOfOrder { id: u64, previous: u64 },
+    /// Two interactions share the same id
+    DuplicateInteraction { id: u64 },
+    /// [Replay::frames] does not match the frame of the last event
+    FrameCountMismatch { frames: u64, last_frame: u32 },
+}
+
+impl Problem {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Problem::FrameOutOfOrder { .. }
+            | Problem::SubframeOutOfRange { .. }
+            | Problem::InputBeforeFull
+            | Problem::MissingFull => Severity::Error,
+            Problem::KeyAlreadyDown { .. }
+            | Problem::KeyNotDown { .. }
+            | Problem::KeyNeverReleased { .. }
+            | Problem::InteractionOutOfOrder { .. }
+            | Problem::DuplicateInteraction { .. }
+            | Problem::FrameCountMismatch { .. } => Severity::Warning,
+        }
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::FrameOutOfOrder { previous } => {
+                write!(f, "event comes after an event on frame {previous}")
+            }
+            Problem::KeyAlreadyDown { key } => write!(f, "{key:?} pressed while already down"),
+            Problem::KeyNotDown { key } => write!(f, "{key:?} released while not down"),
+            Problem::KeyNeverReleased { key } => write!(f, "{key:?} is never released"),
+            Problem::SubframeOutOfRange { subframe } => {
+                write!(f, "subframe {subframe} is outside of [0, 1)")
+            }
+            Problem::InputBeforeFull => write!(f, "input comes before the initial game state"),
+            Problem::MissingFull => write!(f, "the initial game state is missing"),
+            Problem::InteractionOutOfOrder { id, previous } => {
+                write!(f, "interaction {id} comes after interaction {previous}")
+            }
+            Problem::DuplicateInteraction { id } => write!(f, "interaction {id} is repeated"),
+            Problem::FrameCountMismatch { frames, last_frame } => write!(
+                f,
+                "replay is {frames} frames long, but its last event is on frame {last_frame}"
+            ),
+        }
+    }
+}
+
+/// A problem found in a replay, along with where it was found
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// The index of the event the problem was found in, or the number of events if the problem is
+    /// with the replay as a whole
+    pub event: usize,
+    /// The frame the problem was found on
+    pub frame: u32,
+    pub problem: Problem,
+}
+
+impl Diagnostic {
+    pub fn severity(&self) -> Severity {
+        self.problem.severity()
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity() {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(
+            f,
+            "{severity} on frame {} (event {}): {}",
+            self.frame, self.event, self.problem
+        )
+    }
+}
+
+/// Checks a replay for internal consistency, listing every problem found (in the order of the
+/// events they were found in). A replay with no problems gives an empty list.
+pub fn validate(replay: &Replay) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut report = |event, frame, problem| {
+        diagnostics.push(Diagnostic {
+            event,
+            frame,
+            problem,
+        })
+    };
+
+    let mut previous_frame = 0;
+    let mut seen_full = false;
+    let mut ended = false;
+    let mut held = Vec::new();
+    let mut interaction_ids = HashSet::new();
+    // ids increase separately for interactions and their confirmations, which are interleaved
+    let mut last_interaction = None;
+    let mut last_confirmation = None;
+
+    for (index, event) in replay.events.iter().enumerate() {
+        let frame = event.frame;
+        if frame < previous_frame {
+            report(
+                index,
+                frame,
+                Problem::FrameOutOfOrder {
+                    previous: previous_frame,
+                },
+            );
+        }
+        previous_frame = previous_frame.max(frame);
+
+        match event.data {
+            EventData::Full { .. } => seen_full = true,
+            EventData::KeyDown { ref key_event } | EventData::KeyUp { ref key_event } => {
+                if !seen_full {
+                    report(index, frame, Problem::InputBeforeFull);
+                }
+
+                let subframe = key_event.subframe.as_f64().unwrap_or(f64::NAN);
+                if !(0. ..1.).contains(&subframe) {
+                    report(index, frame, Problem::SubframeOutOfRange { subframe });
+                }
+
+                let key = key_event.key;
+                let position = held.iter().position(|&held| held == key);
+                match (&event.data, position) {
+                    (EventData::KeyDown { .. }, None) => held.push(key),
+                    (EventData::KeyDown { .. }, Some(_)) => {
+                        report(index, frame, Problem::KeyAlreadyDown { key })
+                    }
+                    (_, Some(position)) => {
+                        held.remove(position);
+                    }
+                    (_, None) => report(index, frame, Problem::KeyNotDown { key }),
+                }
+            }
+            EventData::InGameEvent { ref event } => {
+                let Some(id) = event.id.as_u64() else {
+                    continue;
+                };
+                if !interaction_ids.insert(id) {
+                    report(index, frame, Problem::DuplicateInteraction { id });
+                }
+
+                let last = match event.data.data {
+                    InteractionData::InteractionDo { .. } => &mut last_interaction,
+                    InteractionData::InteractionConfirm { .. } => &mut last_confirmation,
+                };
+                if let Some(previous) = last.replace(id) {
+                    if id < previous {
+                        report(
+                            index,
+                            frame,
+                            Problem::InteractionOutOfOrder { id, previous },
+                        );
+                    }
+                }
+            }
+            EventData::End { .. } => ended = true,
+            EventData::Start {} | EventData::Targets { .. } => (),
+        }
+    }
+
+    let end = replay.events.len();
+    if !seen_full {
+        report(end, previous_frame, Problem::MissingFull);
+    }
+    if !ended {
+        for key in held {
+            report(end, previous_frame, Problem::KeyNeverReleased { key });
+        }
+    }
+    if let (Some(frames), Some(last)) = (replay.frames.as_u64(), replay.events.last()) {
+        // the frame count includes the frame of the last event
+        if frames != last.frame as u64 + 1 {
+            report(
+                end,
+                previous_frame,
+                Problem::FrameCountMismatch {
+                    frames,
+                    last_frame: last.frame,
+                },
+            );
+        }
+    }
+
+    diagnostics
+}
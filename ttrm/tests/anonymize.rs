@@ -0,0 +1,64 @@
+use ttrm::{anonymize::Anonymizer, ParseMode};
+
+#[test]
+fn anonymize_ttrm() {
+    let mut anonymizer = Anonymizer::new();
+    let mut ttrm = ttrm::ttrm_from_slice(
+        include_bytes!("../../samples/HBSQabUhSS.ttrm"),
+        ParseMode::Strict,
+    )
+    .unwrap();
+    anonymizer.anonymize_ttrm(&mut ttrm);
+    assert_eq!(anonymizer.pseudonyms().count(), 2);
+
+    let written = String::from_utf8(ttrm::ttrm_to_vec(&ttrm).unwrap()).unwrap();
+    for identifying in [
+        "zbrachinara",
+        "awsum",
+        "61d4d1a0cfc40273f1f50f57",
+        "614ebaacd6971e44f8af8270",
+        "HBSQabUhSS",
+    ] {
+        assert!(
+            !written.contains(identifying),
+            "{identifying} was not removed"
+        );
+    }
+
+    // the anonymized replay is still a valid replay
+    let ttrm = ttrm::ttrm_from_slice(written.as_bytes(), ParseMode::Strict).unwrap();
+    let names = ttrm
+        .end_context
+        .iter()
+        .map(|player| player.user.username.as_ref())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["player1", "player2"]);
+    assert!(ttrm
+        .data
+        .iter()
+        .all(|set| set.boards[0].user.username == "player1"));
+}
+
+#[test]
+fn stable_pseudonyms() {
+    let mut anonymizer = Anonymizer::new();
+    let mut ttrm = ttrm::ttrm_from_slice(
+        include_bytes!("../../samples/HBSQabUhSS.ttrm"),
+        ParseMode::Strict,
+    )
+    .unwrap();
+    anonymizer.anonymize_ttrm(&mut ttrm);
+
+    // a saved anonymizer gives the same player the same pseudonym in another replay
+    let mut anonymizer: Anonymizer =
+        serde_json::from_str(&serde_json::to_string(&anonymizer).unwrap()).unwrap();
+    let mut ttr =
+        ttrm::ttr_from_slice(include_bytes!("../../samples/_40l.ttr"), ParseMode::Strict).unwrap();
+    anonymizer.anonymize_ttr(&mut ttr);
+
+    assert_eq!(ttr.user.username, "player1");
+    assert_eq!(ttr.id, None);
+    let written = String::from_utf8(ttrm::ttr_to_vec(&ttr).unwrap()).unwrap();
+    assert!(!written.to_lowercase().contains("zbrachinara"));
+    assert!(written.contains("PLAYER1"));
+}
@@ -0,0 +1,70 @@
+use std::{io::Write, path::Path};
+
+use flate2::{write::GzEncoder, Compression as GzLevel};
+use ruzstd::encoding::{compress_to_vec, CompressionLevel};
+use ttrm::{
+    compression::{self, Compression},
+    ParseMode,
+};
+
+const FORTY_LINE: &[u8] = include_bytes!("../../samples/_40l.ttr");
+const MATCH: &[u8] = include_bytes!("../../samples/HBSQabUhSS.ttrm");
+
+fn gzip(bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), GzLevel::default());
+    encoder.write_all(bytes).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn zstd(bytes: &[u8]) -> Vec<u8> {
+    compress_to_vec(bytes, CompressionLevel::Fastest)
+}
+
+#[test]
+fn compressed_ttr() {
+    let original = ttrm::ttr_from_slice(FORTY_LINE, ParseMode::Strict).unwrap();
+
+    for compressed in [gzip(FORTY_LINE), zstd(FORTY_LINE)] {
+        let ttr = ttrm::ttr_from_slice(&compressed, ParseMode::Strict).unwrap();
+        assert_eq!(ttr.data.events.len(), original.data.events.len());
+        assert_eq!(ttr.user.username, original.user.username);
+
+        let ttr = ttrm::ttr_from_reader(compressed.as_slice(), ParseMode::Strict).unwrap();
+        assert_eq!(ttr.data.events.len(), original.data.events.len());
+    }
+}
+
+#[test]
+fn compressed_ttrm() {
+    let original = ttrm::ttrm_from_slice(MATCH, ParseMode::Strict).unwrap();
+
+    for compressed in [gzip(MATCH), zstd(MATCH)] {
+        let ttrm = ttrm::ttrm_from_slice(&compressed, ParseMode::Strict).unwrap();
+        assert_eq!(ttrm.data.len(), original.data.len());
+        assert_eq!(ttrm.short_id, original.short_id);
+
+        // streaming reads straight out of the input, so it has to be decompressed first
+        assert!(ttrm::stream::ttrm_events(&compressed[..]).is_err());
+        let decompressed = compression::decompress(&compressed).unwrap();
+        assert_eq!(&*decompressed, MATCH);
+        assert!(ttrm::stream::ttrm_events(&*decompressed).is_ok());
+    }
+}
+
+#[test]
+fn detect_compression() {
+    assert_eq!(Compression::detect(FORTY_LINE), Compression::None);
+    assert_eq!(Compression::detect(&gzip(FORTY_LINE)), Compression::Gzip);
+    assert_eq!(Compression::detect(&zstd(FORTY_LINE)), Compression::Zstd);
+
+    // damaged files are reported instead of read as json
+    let mut damaged = gzip(FORTY_LINE);
+    damaged.truncate(damaged.len() / 2);
+    assert!(ttrm::ttr_from_slice(&damaged, ParseMode::Strict).is_err());
+
+    let extension = |path| compression::replay_extension(Path::new(path));
+    assert_eq!(extension("replay.ttr"), Some("ttr"));
+    assert_eq!(extension("replay.ttr.gz"), Some("ttr"));
+    assert_eq!(extension("archive/match.ttrm.zst"), Some("ttrm"));
+    assert_eq!(extension("replay.gz"), None);
+}
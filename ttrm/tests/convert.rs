@@ -0,0 +1,86 @@
+use ttrm::{ConvertError, GameType, ParseMode, Ttr, Ttrm};
+
+fn ttr(bytes: &'static [u8]) -> Ttr<'static> {
+    ttrm::ttr_from_slice(bytes, ParseMode::Strict).unwrap()
+}
+
+#[test]
+fn split_match() {
+    let ttrm = ttrm::ttrm_from_slice(
+        include_bytes!("../../samples/HBSQabUhSS.ttrm"),
+        ParseMode::Strict,
+    )
+    .unwrap();
+    let original = ttrm.clone();
+    let rounds = ttrm.into_ttrs().unwrap();
+
+    assert_eq!(rounds.len(), original.data.len());
+    for (round, (ttrs, set)) in rounds.iter().zip(&original.data).enumerate() {
+        assert_eq!(ttrs.len(), set.replays.len());
+        for ttr in ttrs {
+            assert_eq!(ttr.game_type, GameType::League);
+            assert_eq!(ttr.timestamp, original.timestamp);
+            assert_eq!(
+                ttr.data.username().unwrap().to_lowercase(),
+                ttr.user.username.to_lowercase()
+            );
+
+            // the points of each round can be worked out again from the split replays
+            let player = original
+                .end_context
+                .iter()
+                .find(|player| player.user.user_id == ttr.user.user_id)
+                .unwrap();
+            let rejoined = Ttrm::from_ttrs([ttr.clone()]).unwrap();
+            let points = &rejoined.end_context[0].points;
+            assert!((points.apm - player.points.apm_per_round[round]).abs() < 1e-6);
+            assert!((points.pps - player.points.pps_per_round[round]).abs() < 1e-6);
+            assert!((points.extra.vs - player.points.extra_per_round.vs[round]).abs() < 1e-6);
+
+            // and each is a ttr file of its own
+            let written = ttrm::ttr_to_vec(ttr).unwrap();
+            ttrm::ttr_from_slice(&written, ParseMode::Strict).unwrap();
+        }
+    }
+}
+
+#[test]
+fn gather_ttrs() {
+    let forty_line = ttr(include_bytes!("../../samples/_40l.ttr"));
+    let ttrm = Ttrm::from_ttrs([forty_line.clone(), forty_line.clone()]).unwrap();
+
+    assert_eq!(ttrm.game_type, GameType::FortyLine);
+    assert_eq!(ttrm.data.len(), 2);
+    assert_eq!(ttrm.end_context.len(), 1);
+    let player = &ttrm.end_context[0];
+    assert_eq!(player.user.username, forty_line.user.username);
+    assert_eq!(player.wins, 2);
+    assert_eq!(
+        player.pieces_placed,
+        2 * forty_line.end_context.pieces_placed
+    );
+    assert_eq!(player.points.pps_per_round.len(), 2);
+    assert!(ttrm.data.iter().all(|set| set.boards[0].success));
+
+    let written = ttrm::ttrm_to_vec(&ttrm).unwrap();
+    let read = ttrm::ttrm_from_slice(&written, ParseMode::Strict).unwrap();
+    let split = read.into_ttrs().unwrap();
+    assert_eq!(split.len(), 2);
+    assert_eq!(split[0][0].user.user_id, forty_line.user.user_id);
+    assert_eq!(split[0][0].end_context.lines, 40);
+}
+
+#[test]
+fn mixed_game_types() {
+    let forty_line = ttr(include_bytes!("../../samples/_40l.ttr"));
+    let blitz = ttr(include_bytes!("../../samples/blitz.ttr"));
+
+    assert_eq!(
+        Ttrm::from_ttrs([forty_line, blitz]).unwrap_err(),
+        ConvertError::MixedGameTypes {
+            first: GameType::FortyLine,
+            other: GameType::Blitz
+        }
+    );
+    assert_eq!(Ttrm::from_ttrs([]).unwrap_err(), ConvertError::NoReplays);
+}
@@ -22,3 +22,101 @@ fn custom_board_ttr() {
             .unwrap()
     );
 }
+
+#[test]
+fn forty_line_objective() {
+    use ttrm::event::{EventData, ObjectiveKind};
+
+    let ttr = ttrm::ttr_from_slice(
+        include_bytes!("../../samples/_40l.ttr"),
+        ttrm::ParseMode::Strict,
+    )
+    .unwrap();
+    let full = ttr
+        .data
+        .events
+        .iter()
+        .find_map(|event| match event.data {
+            EventData::Full { ref data } => Some(data),
+            _ => None,
+        })
+        .unwrap();
+
+    assert_eq!(full.options.objective.kind, ObjectiveKind::Lines);
+    assert_eq!(full.options.objective.count, Some(40));
+    assert_eq!(full.stats.seed, full.options.seed);
+}
+
+#[test]
+fn unknown_options() {
+    use ttrm::ParseMode;
+
+    let bytes = std::str::from_utf8(include_bytes!("../../samples/_40l.ttr"))
+        .unwrap()
+        .replacen(r#""version":15"#, r#""version":15,"newoption":true"#, 1);
+
+    assert!(ttrm::ttr_from_slice(bytes.as_bytes(), ParseMode::Strict).is_err());
+
+    let ttr = ttrm::ttr_from_slice(bytes.as_bytes(), ParseMode::Lenient).unwrap();
+    let unknown = ttr.unknown_options();
+    assert_eq!(unknown.len(), 1);
+    assert_eq!(unknown[0].key, "newoption");
+    assert_eq!(unknown[0].version, 15);
+}
+
+#[test]
+fn owned_ttr() {
+    let ttr = {
+        let file =
+            std::fs::File::open(concat!(env!("CARGO_MANIFEST_DIR"), "/../samples/blitz.ttr"))
+                .unwrap();
+        ttrm::ttr_from_reader(file, ttrm::ParseMode::Strict).unwrap()
+    };
+
+    assert_eq!(ttr.game_type, ttrm::GameType::Blitz);
+    assert!(!ttr.data.events.is_empty());
+}
+
+#[test]
+fn error_location() {
+    let bytes = std::str::from_utf8(include_bytes!("../../samples/_40l.ttr"))
+        .unwrap()
+        .replacen(r#""garbagecap":8"#, r#""garbagecap":"eight""#, 1);
+
+    let error = ttrm::ttr_from_slice(bytes.as_bytes(), ttrm::ParseMode::Lenient).unwrap_err();
+    assert!(error.path.starts_with("data.events["), "{error}");
+    assert!(error.path.ends_with("].data.options.garbagecap"), "{error}");
+    assert_eq!(error.frame, Some(0));
+    assert_eq!(error.value.as_deref(), Some(r#""eight""#));
+
+    let bytes = std::str::from_utf8(include_bytes!("../../samples/_40l.ttr"))
+        .unwrap()
+        .replacen(r#""version":15"#, r#""version":15,"newoption":true"#, 1);
+
+    let error = ttrm::ttr_from_slice(bytes.as_bytes(), ttrm::ParseMode::Strict).unwrap_err();
+    assert!(error.path.ends_with(".data.options.newoption"), "{error}");
+}
+
+#[test]
+fn option_enums() {
+    use ttrm::event::{EventData, KickSet, SlotCounter, ZoomInto};
+
+    let bytes = std::str::from_utf8(include_bytes!("../../samples/_40l.ttr"))
+        .unwrap()
+        .replacen(r#""kickset":"SRS+""#, r#""kickset":"SRS-Z""#, 1);
+    let ttr = ttrm::ttr_from_slice(bytes.as_bytes(), ttrm::ParseMode::Strict).unwrap();
+    let options = ttr
+        .data
+        .events
+        .iter()
+        .find_map(|event| match event.data {
+            EventData::Full { ref data } => Some(&data.options),
+            _ => None,
+        })
+        .unwrap();
+
+    assert_eq!(options.bag_type, None);
+    assert_eq!(options.kickset, KickSet::Other("SRS-Z".to_owned()));
+    assert_eq!(options.zoom_into, ZoomInto::Slow);
+    assert_eq!(options.slot_counter1, Some(SlotCounter::Stopwatch));
+}
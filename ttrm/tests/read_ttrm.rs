@@ -6,3 +6,131 @@ fn s_plus_game() {
             .unwrap()
     );
 }
+
+#[test]
+fn league_targets() {
+    use ttrm::event::{EventData, Targets};
+
+    let ttrm =
+        serde_json::from_slice::<ttrm::Ttrm>(include_bytes!("../../samples/HBSQabUhSS.ttrm"))
+            .unwrap();
+
+    for set in &ttrm.data {
+        for (replay, board) in set.replays.iter().zip(&set.boards) {
+            let opponent = set
+                .boards
+                .iter()
+                .find(|other| other.user.user_id != board.user.user_id)
+                .unwrap();
+
+            let targets = replay
+                .events
+                .iter()
+                .find_map(|event| match event.data {
+                    EventData::Targets {
+                        data: Targets { ref targets, .. },
+                    } => Some(targets),
+                    _ => None,
+                })
+                .unwrap();
+
+            assert_eq!(
+                targets.as_slice(),
+                std::slice::from_ref(&opponent.user.user_id)
+            );
+        }
+    }
+}
+
+#[test]
+fn owned_ttrm() {
+    let ttrm = {
+        let bytes = include_bytes!("../../samples/HBSQabUhSS.ttrm").to_vec();
+        ttrm::ttrm_from_slice(&bytes, ttrm::ParseMode::Strict)
+            .unwrap()
+            .into_owned()
+    };
+
+    assert_eq!(ttrm.data[0].boards[0].user.username, "zbrachinara");
+}
+
+#[test]
+fn streamed_events() {
+    let bytes = include_bytes!("../../samples/HBSQabUhSS.ttrm");
+    let ttrm = ttrm::ttrm_from_slice(bytes, ttrm::ParseMode::Strict).unwrap();
+    let stream = ttrm::stream::ttrm_events(&bytes[..]).unwrap();
+
+    let streamed = stream.map(|event| event.unwrap()).collect::<Vec<_>>();
+    let parsed = ttrm
+        .data
+        .iter()
+        .enumerate()
+        .flat_map(|(round, set)| {
+            set.replays
+                .iter()
+                .enumerate()
+                .flat_map(move |(player, replay)| {
+                    replay
+                        .events
+                        .iter()
+                        .map(move |event| (round, player, event))
+                })
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(streamed.len(), parsed.len());
+    for (streamed, (round, player, event)) in streamed.iter().zip(parsed) {
+        assert_eq!((streamed.round, streamed.player), (round, player));
+        assert_eq!(streamed.event.frame, event.frame);
+    }
+
+    // files written by this crate can be streamed as well
+    let written = ttrm::ttrm_to_vec(&ttrm).unwrap();
+    let stream = ttrm::stream::ttrm_events(&written[..]).unwrap();
+    assert_eq!(stream.map(Result::unwrap).count(), streamed.len());
+}
+
+#[test]
+fn streamed_error_location() {
+    let bytes = std::str::from_utf8(include_bytes!("../../samples/HBSQabUhSS.ttrm"))
+        .unwrap()
+        .replacen(r#""garbagecap":8"#, r#""garbagecap":-1"#, 1);
+
+    let error = ttrm::stream::ttrm_events(bytes.as_bytes())
+        .unwrap()
+        .find_map(Result::err)
+        .unwrap();
+    assert!(
+        error.path.starts_with("data[0].replays[0].events["),
+        "{error}"
+    );
+    assert!(error.path.ends_with("].data.options.garbagecap"), "{error}");
+    assert_eq!(error.value.as_deref(), Some("-1"));
+}
+
+#[test]
+fn match_end_context() {
+    let ttrm =
+        serde_json::from_slice::<ttrm::Ttrm>(include_bytes!("../../samples/HBSQabUhSS.ttrm"))
+            .unwrap();
+
+    let wins = ttrm
+        .end_context
+        .iter()
+        .map(|player| (player.user.username.as_ref(), player.wins))
+        .collect::<Vec<_>>();
+    assert_eq!(wins, [("zbrachinara", 3), ("awsum", 5)]);
+
+    for player in &ttrm.end_context {
+        let rounds_won = ttrm
+            .data
+            .iter()
+            .flat_map(|set| &set.boards)
+            .filter(|board| board.user.user_id == player.user.user_id && board.success)
+            .count();
+        assert_eq!(rounds_won, player.points.wins as usize);
+        assert_eq!(player.points.apm_per_round.len(), ttrm.data.len());
+        assert_eq!(player.points.pps_per_round.len(), ttrm.data.len());
+        assert_eq!(player.points.extra_per_round.vs.len(), ttrm.data.len());
+    }
+}
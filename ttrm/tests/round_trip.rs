@@ -0,0 +1,56 @@
+use serde_json::Value;
+
+/// Lists every place where the written json differs from the original
+fn differences(path: String, original: &Value, written: &Value, out: &mut Vec<String>) {
+    match (original, written) {
+        (Value::Object(a), Value::Object(b)) => {
+            for key in a.keys().chain(b.keys().filter(|k| !a.contains_key(*k))) {
+                match (a.get(key), b.get(key)) {
+                    (Some(x), Some(y)) => differences(format!("{path}.{key}"), x, y, out),
+                    (x, y) => out.push(format!("{path}.{key}: {x:?} => {y:?}")),
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => {
+            for (i, (x, y)) in a.iter().zip(b).enumerate() {
+                differences(format!("{path}[{i}]"), x, y, out)
+            }
+        }
+        (x, y) if x != y => out.push(format!("{path}: {x} => {y}")),
+        _ => (),
+    }
+}
+
+#[test]
+fn round_trip_samples() {
+    let samples = std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/../samples")).unwrap();
+    for sample in samples {
+        let path = sample.unwrap().path();
+        let bytes = std::fs::read(&path).unwrap();
+
+        let written = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("ttr") => {
+                ttrm::ttr_to_vec(&ttrm::ttr_from_slice(&bytes, ttrm::ParseMode::Strict).unwrap())
+            }
+            Some("ttrm") => {
+                ttrm::ttrm_to_vec(&ttrm::ttrm_from_slice(&bytes, ttrm::ParseMode::Strict).unwrap())
+            }
+            _ => continue,
+        }
+        .unwrap();
+
+        let mut found = Vec::new();
+        differences(
+            String::new(),
+            &serde_json::from_slice(&bytes).unwrap(),
+            &serde_json::from_slice(&written).unwrap(),
+            &mut found,
+        );
+        assert!(
+            found.is_empty(),
+            "{} differs after writing:\n{}",
+            path.display(),
+            found[..found.len().min(20)].join("\n")
+        );
+    }
+}
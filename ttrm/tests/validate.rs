@@ -0,0 +1,112 @@
+use ttrm::{event::EventData, Diagnostic, ParseMode, Problem, Severity};
+
+#[test]
+fn valid_samples() {
+    for sample in [
+        &include_bytes!("../../samples/_40l.ttr")[..],
+        include_bytes!("../../samples/blitz.ttr"),
+        include_bytes!("../../samples/garbage.ttr"),
+        include_bytes!("../../samples/hahahaki.ttr"),
+        include_bytes!("../../samples/large_board.ttr"),
+        include_bytes!("../../samples/zbrachi_standard.ttr"),
+    ] {
+        let ttr = ttrm::ttr_from_slice(sample, ParseMode::Lenient).unwrap();
+        assert_eq!(ttrm::validate(&ttr.data), []);
+    }
+
+    let ttrm = ttrm::ttrm_from_slice(
+        include_bytes!("../../samples/HBSQabUhSS.ttrm"),
+        ParseMode::Lenient,
+    )
+    .unwrap();
+    for set in &ttrm.data {
+        for replay in &set.replays {
+            assert_eq!(ttrm::validate(replay), []);
+        }
+    }
+}
+
+#[test]
+fn invalid_replay() {
+    let mut ttr =
+        ttrm::ttr_from_slice(include_bytes!("../../samples/_40l.ttr"), ParseMode::Lenient).unwrap();
+    let events = &mut ttr.data.events;
+
+    let inputs = events
+        .iter()
+        .enumerate()
+        .filter(|(_, event)| matches!(event.data, EventData::KeyDown { .. }))
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+
+    // an input which happens before the one preceding it
+    let early = inputs[10];
+    events[early].frame = events[early - 1].frame - 1;
+    let early_frame = events[early].frame;
+
+    // an input at the end of its frame
+    let late = inputs[20];
+    let EventData::KeyDown { ref mut key_event } = events[late].data else {
+        unreachable!()
+    };
+    key_event.subframe = 1.into();
+
+    // a release of a key which was never pressed
+    let released = inputs[30];
+    let EventData::KeyDown { ref key_event } = events[released].data else {
+        unreachable!()
+    };
+    let key_event = key_event.clone();
+    events[released].data = EventData::KeyUp { key_event };
+
+    let diagnostics = ttrm::validate(&ttr.data);
+    let events = &ttr.data.events;
+    let problems = diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.event, diagnostic.frame, &diagnostic.problem))
+        .collect::<Vec<_>>();
+
+    assert!(problems.contains(&(
+        early,
+        early_frame,
+        &Problem::FrameOutOfOrder {
+            previous: events[early - 1].frame
+        }
+    )));
+    assert!(problems.contains(&(
+        late,
+        events[late].frame,
+        &Problem::SubframeOutOfRange { subframe: 1. }
+    )));
+    assert!(diagnostics.iter().any(|diagnostic| matches!(
+        diagnostic,
+        Diagnostic {
+            event,
+            problem: Problem::KeyNotDown { .. },
+            ..
+        } if *event == released
+    )));
+    assert!(diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity() == Severity::Error));
+}
+
+#[test]
+fn missing_full() {
+    let mut ttr =
+        ttrm::ttr_from_slice(include_bytes!("../../samples/_40l.ttr"), ParseMode::Lenient).unwrap();
+    ttr.data
+        .events
+        .retain(|event| !matches!(event.data, EventData::Full { .. }));
+    ttr.data.frames = 1.into();
+
+    let problems = ttrm::validate(&ttr.data)
+        .into_iter()
+        .map(|diagnostic| diagnostic.problem)
+        .collect::<Vec<_>>();
+    assert!(problems.contains(&Problem::InputBeforeFull));
+    assert!(problems.contains(&Problem::MissingFull));
+    assert!(problems
+        .iter()
+        .any(|problem| matches!(problem, Problem::FrameCountMismatch { frames: 1, .. })));
+}
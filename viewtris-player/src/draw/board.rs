@@ -101,6 +101,9 @@ impl Board {
                 }
                 self.holds_passed += 1;
             }
+            ActionKind::Lock { .. }
+            | ActionKind::Attack { .. }
+            | ActionKind::GarbageCancel { .. } => {}
         }
     }
 
@@ -134,6 +137,9 @@ impl Board {
                 self.hold = None;
             }
             ActionKind::Hold => {}
+            ActionKind::Lock { .. }
+            | ActionKind::Attack { .. }
+            | ActionKind::GarbageCancel { .. } => {}
         }
     }
 }
@@ -1,4 +1,4 @@
-use std::{fs, os::unix::prelude::OsStrExt};
+use std::fs;
 
 use itertools::Itertools;
 use macroquad::prelude::*;
@@ -8,32 +8,103 @@ fn screen_rect() -> Rect {
 }
 
 use crate::{selection::Selection, state::ReplayState};
+use tap::TapOptional;
+use tetrio_replay::ttrm::{compression, ParseMode, Replay, Ttrm, UnknownOption};
 
+/// Opens a replay chosen by the user, which may be compressed (such as `replay.ttr.gz`)
 pub fn open_file() -> Result<Selection, ()> {
     rfd::FileDialog::new()
         .pick_file()
-        .and_then(|fi| {
-            fs::read(fi.clone())
-                .map(|buf| (buf, fi.extension().map(|str| str.to_os_string())))
-                .ok()
-        })
-        .and_then(|(buf, extension)| match extension {
-            Some(x) if x.as_bytes() == b"ttr" => read_ttr(buf.as_slice()),
-            Some(x) if x.as_bytes() == b"ttrm" => read_ttrm(buf.as_slice()),
+        .and_then(|fi| fs::read(&fi).map(|buf| (buf, fi)).ok())
+        .and_then(|(buf, fi)| match compression::replay_extension(&fi) {
+            Some("ttr") => read_ttr(buf.as_slice()),
+            Some("ttrm") => read_ttrm(buf.as_slice()),
+            Some("jstris") => read_jstris(buf.as_slice()),
             _ => {
-                eprintln!("Unknown file type, this player only expects ttr or ttrm ");
+                eprintln!("Unknown file type, this player only expects ttr, ttrm or jstris");
                 None
             }
         })
         .ok_or(())
 }
 
+fn warn_unknown(unknown: Vec<UnknownOption>) {
+    unknown
+        .iter()
+        .for_each(|option| eprintln!("Warning: {option}"))
+}
+
+fn report_problems(replay: &Replay) {
+    tetrio_replay::ttrm::validate(replay)
+        .iter()
+        .for_each(|diagnostic| eprintln!("{diagnostic}"))
+}
+
+/// Describes the result of a match, such as "alice 7 - 4 bob"
+fn match_score(ttrm: &Ttrm) -> String {
+    ttrm.end_context
+        .iter()
+        .sorted_by_key(|player| player.natural_order)
+        .map(|player| format!("{} {}", player.user.username, player.wins))
+        .join(" - ")
+}
+
+/// Describes a round of a match by its winner and the versus score of each player
+fn round_label(ttrm: &Ttrm, round: usize) -> String {
+    let winner = ttrm.data[round]
+        .boards
+        .iter()
+        .find(|board| board.success)
+        .map_or("nobody", |board| board.user.username.as_ref());
+    let scores = ttrm
+        .end_context
+        .iter()
+        .sorted_by_key(|player| player.natural_order)
+        .filter_map(|player| {
+            let vs = player.points.extra_per_round.vs.get(round)?;
+            Some(format!("{} {vs:.1} VS", player.user.username))
+        })
+        .join(", ");
+
+    format!("Round {}: won by {winner} ({scores})", round + 1)
+}
+
 fn read_ttr(buf: &[u8]) -> Option<Selection> {
-    tetrio_replay::ttrm::ttr_from_slice(buf)
+    tetrio_replay::ttrm::ttr_from_slice(buf, ParseMode::Lenient)
+        .map_err(|error| eprintln!("Could not read replay: {error}"))
+        .ok()
+        .tap_some(|ttr| warn_unknown(ttr.unknown_options()))
+        .tap_some(|ttr| report_problems(&ttr.data))
+        .and_then(|ttr| tetrio_replay::reconstruct(&ttr.game_type, ttr.data.events.as_slice()).ok())
+        .map(|actions| Selection {
+            replays: vec![ReplayState::with_actions([actions])],
+            title: None,
+            labels: Vec::new(),
+            camera: Camera2D::from_display_rect(screen_rect()),
+            selected: 0,
+            in_replay: true,
+        })
+}
+
+fn read_jstris(buf: &[u8]) -> Option<Selection> {
+    // unlike tetrio replays, jstris replays are not decompressed by the crate which reads them
+    compression::decompress(buf)
+        .map_err(|error| eprintln!("Could not decompress replay: {error}"))
         .ok()
-        .and_then(|ttr| tetrio_replay::reconstruct(ttr.game_type, ttr.data.events.as_slice()).ok())
+        .and_then(|buf| {
+            jstris_replay::Replay::from_slice(&buf)
+                .map_err(|error| eprintln!("Could not read replay: {error}"))
+                .ok()
+        })
+        .and_then(|replay| {
+            jstris_replay::reconstruct(&replay)
+                .map_err(|error| eprintln!("Could not play back replay: {error}"))
+                .ok()
+        })
         .map(|actions| Selection {
             replays: vec![ReplayState::with_actions([actions])],
+            title: None,
+            labels: Vec::new(),
             camera: Camera2D::from_display_rect(screen_rect()),
             selected: 0,
             in_replay: true,
@@ -41,26 +112,37 @@ fn read_ttr(buf: &[u8]) -> Option<Selection> {
 }
 
 fn read_ttrm(buf: &[u8]) -> Option<Selection> {
-    tetrio_replay::ttrm::ttrm_from_slice(buf)
+    tetrio_replay::ttrm::ttrm_from_slice(buf, ParseMode::Lenient)
+        .map_err(|error| eprintln!("Could not read replay: {error}"))
         .ok()
+        .tap_some(|ttrm| warn_unknown(ttrm.unknown_options()))
         .and_then(|ttrm| {
-            let replays = ttrm
+            let (labels, replays): (Vec<_>, Vec<_>) = ttrm
                 .data
                 .iter()
-                .filter_map(|player| {
+                .enumerate()
+                .filter_map(|(round, player)| {
                     player
                         .replays
                         .iter()
                         .map(|replay| {
-                            tetrio_replay::reconstruct(ttrm.game_type, &replay.events).ok()
+                            report_problems(replay);
+                            tetrio_replay::reconstruct(&ttrm.game_type, &replay.events).ok()
                         })
                         .collect::<Option<Vec<_>>>()
-                        .map(ReplayState::with_actions)
+                        .map(|actions| {
+                            (
+                                round_label(&ttrm, round),
+                                ReplayState::with_actions(actions),
+                            )
+                        })
                 })
-                .collect_vec();
+                .unzip();
 
             (!replays.is_empty()).then_some(Selection {
                 replays,
+                title: Some(match_score(&ttrm)),
+                labels,
                 camera: Camera2D::from_display_rect(screen_rect()),
                 selected: 0,
                 in_replay: false,
@@ -5,6 +5,10 @@ use crate::state::ReplayState;
 #[derive(Default)]
 pub struct Selection {
     pub replays: Vec<ReplayState>,
+    /// A summary of the file the replays were read from, such as the score of a match
+    pub title: Option<String>,
+    /// A description of each replay, shown in place of its number if present
+    pub labels: Vec<String>,
     pub camera: Camera2D,
     pub selected: usize,
     pub in_replay: bool,
@@ -28,10 +32,13 @@ impl Selection {
             let h = 30.;
             let y_root = (screen_height() - self.replays.len() as f32 * h) / 2.;
 
-            // TODO encode and show information about replay states
             for (i, _) in self.replays.iter().enumerate() {
                 let y = y_root + i as f32 * h;
-                let text = format!("Replay {}", i + 1);
+                let text = self
+                    .labels
+                    .get(i)
+                    .cloned()
+                    .unwrap_or_else(|| format!("Replay {}", i + 1));
                 if i == self.selected {
                     draw_rectangle(x, y, w, h, WHITE);
                     draw_text(text.as_str(), x + 10., y + h / 2., 16., BLACK);
@@ -41,7 +48,12 @@ impl Selection {
                 }
             }
 
-            draw_text("REPLAY SELECTION", x, y_root - 40., 32., WHITE)
+            if let Some(ref title) = self.title {
+                draw_text(title, x, y_root - 20., 20., WHITE);
+                draw_text("REPLAY SELECTION", x, y_root - 50., 32., WHITE)
+            } else {
+                draw_text("REPLAY SELECTION", x, y_root - 40., 32., WHITE)
+            }
         }
     }
 
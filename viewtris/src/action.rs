@@ -1,4 +1,7 @@
-use crate::tetromino::{Cell, Mino};
+use crate::{
+    tables::damage_table::Spin,
+    tetromino::{Cell, Mino},
+};
 
 /// An action is something which can happen to a player's board, though it is not recorded whether
 /// or not this is a consequence of the player's actions themselves. They may happen in the same
@@ -17,6 +20,15 @@ pub enum ActionKind {
     /// Changes a cell at the given position. This is not limited to spawning a cell, and can also
     /// mark one's removal
     Cell { position: (u8, u8), kind: Cell },
+    /// The active piece locks into place, which follows the cells it leaves behind. Says whether
+    /// the piece was spun into place.
+    Lock { spin: Spin },
+    /// The piece which just locked sends an attack of the given amount, some of which may go
+    /// towards cancelling incoming garbage
+    Attack { amount: u16 },
+    /// Incoming garbage of the given amount is cancelled out by an attack before reaching the board,
+    /// which leaves the board as it is
+    GarbageCancel { amount: u16 },
     /// Activates the hold function, which usually means swapping the active piece with a piece in
     /// an independently managed queue (usually one piece long).
     Hold,
@@ -0,0 +1,731 @@
+//! Reading and writing fumen (version 115), the format positions are commonly shared in. A fumen
+//! is a series of pages, each showing a field and optionally a piece placed on it along with a
+//! comment. Pages after the first are written as changes to the field the page before leaves
+//! behind, which is the field after its piece has locked (if it is set to lock).
+//!
+//! Fumen has no notion of a hold or a queue, so these are written into the comment as in fumen's
+//! quiz mode: `#Q=[hold](current)queue`.
+
+use std::fmt::Display;
+
+use crate::{
+    action::{Action, ActionKind},
+    tetromino::{Cell, Direction, Mino, MinoVariant},
+};
+
+/// The width of a fumen field
+pub const FIELD_WIDTH: usize = 10;
+/// The height of a fumen field, not counting the garbage row below it
+pub const FIELD_HEIGHT: usize = 23;
+/// The number of cells written for each page, which includes the garbage row
+const FIELD_BLOCKS: u32 = ((FIELD_HEIGHT + 1) * FIELD_WIDTH) as u32;
+
+const PREFIX: &str = "v115@";
+const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+/// The characters comments are written with, after being escaped
+const COMMENT_TABLE: &[u8] =
+    b" !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+const MAX_COMMENT_LENGTH: usize = 4095;
+const QUIZ_PREFIX: &str = "#Q=";
+
+/// A row of a fumen field
+pub type Row = [Cell; FIELD_WIDTH];
+
+/// One page of a fumen
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    /// The rows of the field, from the bottom up. There are always [FIELD_HEIGHT] of them.
+    pub field: Vec<Row>,
+    /// The row waiting below the field, which is pushed into it if [Self::rise] is set
+    pub garbage: Row,
+    /// The piece shown on the field, which is placed there if [Self::lock] is set
+    pub active: Option<Mino>,
+    pub hold: Option<MinoVariant>,
+    pub queue: Vec<MinoVariant>,
+    pub comment: String,
+    /// Whether the active piece locks before the next page, clearing any lines it completes
+    pub lock: bool,
+    /// Whether the garbage row is pushed into the field before the next page
+    pub rise: bool,
+    /// Whether the field is flipped horizontally before the next page
+    pub mirror: bool,
+}
+
+impl Default for Page {
+    fn default() -> Self {
+        Self {
+            field: vec![[Cell::Empty; FIELD_WIDTH]; FIELD_HEIGHT],
+            garbage: [Cell::Empty; FIELD_WIDTH],
+            active: None,
+            hold: None,
+            queue: Vec::new(),
+            comment: String::new(),
+            lock: true,
+            rise: false,
+            mirror: false,
+        }
+    }
+}
+
+/// Why a fumen could not be read
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeError {
+    /// The fumen is not of version 115
+    UnsupportedVersion,
+    InvalidCharacter(char),
+    /// The fumen stops partway through a page
+    UnexpectedEnd,
+    /// The changes to the field run past its last cell, or make cells out of range
+    InvalidField,
+    /// A comment is not made of the characters comments are written with
+    InvalidComment,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::UnsupportedVersion => write!(f, "only v115 fumens can be read"),
+            DecodeError::InvalidCharacter(c) => write!(f, "`{c}` cannot appear in a fumen"),
+            DecodeError::UnexpectedEnd => write!(f, "the fumen stops partway through a page"),
+            DecodeError::InvalidField => write!(f, "the fumen describes an invalid field"),
+            DecodeError::InvalidComment => write!(f, "the fumen has an invalid comment"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// The number fumen uses for a cell
+fn cell_number(cell: Cell) -> u32 {
+    match cell {
+        Cell::Empty => 0,
+        Cell::Tetromino(MinoVariant::I) => 1,
+        Cell::Tetromino(MinoVariant::L) => 2,
+        Cell::Tetromino(MinoVariant::O) => 3,
+        Cell::Tetromino(MinoVariant::Z) => 4,
+        Cell::Tetromino(MinoVariant::T) => 5,
+        Cell::Tetromino(MinoVariant::J) => 6,
+        Cell::Tetromino(MinoVariant::S) => 7,
+        Cell::Garbage => 8,
+    }
+}
+
+fn number_cell(number: u32) -> Option<Cell> {
+    use MinoVariant::*;
+    Some(match number {
+        0 => Cell::Empty,
+        8 => Cell::Garbage,
+        1..=7 => Cell::Tetromino([I, L, O, Z, T, J, S][number as usize - 1]),
+        _ => return None,
+    })
+}
+
+/// The number fumen uses for a rotation
+fn direction_number(direction: Direction) -> u32 {
+    match direction {
+        Direction::Down => 0,
+        Direction::Right => 1,
+        Direction::Up => 2,
+        Direction::Left => 3,
+    }
+}
+
+fn number_direction(number: u32) -> Direction {
+    [
+        Direction::Down,
+        Direction::Right,
+        Direction::Up,
+        Direction::Left,
+    ][number as usize]
+}
+
+/// The cells of a piece placed at (0, 0) by fumen, which rotates every piece (even the O) around
+/// one of its cells
+fn fumen_cells(variant: MinoVariant, direction: Direction) -> [(isize, isize); 4] {
+    use MinoVariant::*;
+    let spawn = match variant {
+        I => [(0, 0), (-1, 0), (1, 0), (2, 0)],
+        T => [(0, 0), (-1, 0), (1, 0), (0, 1)],
+        O => [(0, 0), (1, 0), (0, 1), (1, 1)],
+        L => [(0, 0), (-1, 0), (1, 0), (1, 1)],
+        J => [(0, 0), (-1, 0), (1, 0), (-1, 1)],
+        S => [(0, 0), (-1, 0), (0, 1), (1, 1)],
+        Z => [(0, 0), (1, 0), (0, 1), (-1, 1)],
+    };
+    spawn.map(|(x, y)| match direction {
+        Direction::Up => (x, y),
+        Direction::Right => (y, -x),
+        Direction::Down => (-x, -y),
+        Direction::Left => (-y, x),
+    })
+}
+
+/// The lowest (and then leftmost) of a set of cells, which two sets of cells of the same shape can
+/// be lined up by
+fn lowest(cells: &[(isize, isize)]) -> (isize, isize) {
+    cells.iter().copied().min_by_key(|&(x, y)| (y, x)).unwrap()
+}
+
+/// The position fumen gives to a piece, or `None` if it is not within the field
+fn fumen_position(mino: &Mino) -> Option<(isize, isize)> {
+    let cells = mino.position().0;
+    let fits = cells.iter().all(|&(x, y)| {
+        (0..FIELD_WIDTH as isize).contains(&x) && (0..FIELD_HEIGHT as isize).contains(&y)
+    });
+    if !fits {
+        return None;
+    }
+    let (lowest_x, lowest_y) = lowest(&cells);
+    let (origin_x, origin_y) = lowest(&fumen_cells(mino.variant, mino.direction));
+    Some((lowest_x - origin_x, lowest_y - origin_y))
+}
+
+/// Moves a mino so that it takes up the given cells, if they are of the same shape
+fn align(mino: Mino, cells: &[(isize, isize)]) -> Mino {
+    let (x, y) = lowest(cells);
+    let (from_x, from_y) = lowest(&mino.position().0);
+    Mino {
+        coord: (
+            mino.coord.0 + (x - from_x) as i16,
+            mino.coord.1 + (y - from_y) as i16,
+        ),
+        ..mino
+    }
+}
+
+/// The piece fumen places at the given position
+fn mino_at(variant: MinoVariant, direction: Direction, (x, y): (isize, isize)) -> Mino {
+    let cells = fumen_cells(variant, direction).map(|(cell_x, cell_y)| (cell_x + x, cell_y + y));
+    let mino = Mino {
+        variant,
+        direction,
+        coord: (0, 0),
+    };
+    align(mino, &cells)
+}
+
+/// How far the position fumen writes is from the position of the piece, for the pieces which the
+/// original fumen placed by a different cell
+fn written_offset(variant: MinoVariant, direction: Direction) -> (isize, isize) {
+    match (variant, direction) {
+        (MinoVariant::O, Direction::Left) => (-1, 1),
+        (MinoVariant::O, Direction::Down) => (-1, 0),
+        (MinoVariant::O, Direction::Up) => (0, 1),
+        (MinoVariant::I, Direction::Down) => (-1, 0),
+        (MinoVariant::S, Direction::Up) => (0, 1),
+        (MinoVariant::Z, Direction::Right) => (1, 0),
+        _ => (0, 0),
+    }
+}
+
+/// Escapes a string as javascript's `escape` does
+fn escape(s: &str) -> String {
+    s.encode_utf16()
+        .map(|unit| match char::from_u32(unit as u32) {
+            Some(c) if c.is_ascii_alphanumeric() || "@*_+-./".contains(c) => c.to_string(),
+            _ if unit < 256 => format!("%{unit:02X}"),
+            _ => format!("%u{unit:04X}"),
+        })
+        .collect()
+}
+
+/// Undoes [escape], as javascript's `unescape` does
+fn unescape(s: &str) -> String {
+    let mut units = Vec::new();
+    let mut rest = s;
+    while let Some(c) = rest.chars().next() {
+        let hex = |digits: &str| u16::from_str_radix(digits, 16).ok();
+        let escaped = match rest.as_bytes() {
+            [b'%', b'u', ..] => rest.get(2..6).and_then(hex).map(|unit| (unit, 6)),
+            [b'%', ..] => rest.get(1..3).and_then(hex).map(|unit| (unit, 3)),
+            _ => None,
+        };
+        match escaped {
+            Some((unit, length)) => {
+                units.push(unit);
+                rest = &rest[length..];
+            }
+            None => {
+                units.extend(c.encode_utf16(&mut [0; 2]).iter());
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    String::from_utf16_lossy(&units)
+}
+
+/// Parses a comment in the style of fumen's quiz mode into the hold, queue and the rest of the
+/// comment
+fn parse_quiz(comment: &str) -> Option<(Option<MinoVariant>, Vec<MinoVariant>, String)> {
+    let quiz = comment.strip_prefix(QUIZ_PREFIX)?;
+    let (quiz, rest) = quiz.split_once(';').unwrap_or((quiz, ""));
+    let piece = |name: &str| name.parse::<MinoVariant>().ok();
+
+    let (hold, quiz) = quiz.strip_prefix('[')?.split_once(']')?;
+    let hold = if hold.is_empty() {
+        None
+    } else {
+        Some(piece(hold)?)
+    };
+    let (_current, queue) = quiz.strip_prefix('(')?.split_once(')')?;
+    let queue = queue
+        .chars()
+        .map(|c| piece(c.encode_utf8(&mut [0; 4])))
+        .collect::<Option<Vec<_>>>()?;
+    Some((hold, queue, rest.to_owned()))
+}
+
+impl Page {
+    /// The comment written for the page, including the hold and queue
+    fn full_comment(&self) -> String {
+        if self.hold.is_none() && self.queue.is_empty() {
+            return self.comment.clone();
+        }
+        let name = |variant: MinoVariant| format!("{variant:?}");
+        let mut comment = format!(
+            "{QUIZ_PREFIX}[{}]({}){}",
+            self.hold.map(name).unwrap_or_default(),
+            self.active
+                .map(|mino| name(mino.variant))
+                .unwrap_or_default(),
+            self.queue.iter().copied().map(name).collect::<String>(),
+        );
+        if !self.comment.is_empty() {
+            comment.push(';');
+            comment.push_str(&self.comment);
+        }
+        comment
+    }
+
+    /// The field and garbage row the next page starts from
+    fn next_field(&self) -> (Vec<Row>, Row) {
+        let mut field = self.field.clone();
+        let mut garbage = self.garbage;
+        if !self.lock {
+            return (field, garbage);
+        }
+
+        if let Some(active) = self.active.filter(|mino| fumen_position(mino).is_some()) {
+            for (x, y) in active.position().0 {
+                field[y as usize][x as usize] = active.variant.into();
+            }
+        }
+        field.retain(|row| row.iter().any(Cell::is_empty));
+        field.resize(FIELD_HEIGHT, [Cell::Empty; FIELD_WIDTH]);
+        if self.rise {
+            field.insert(0, garbage);
+            field.truncate(FIELD_HEIGHT);
+            garbage = [Cell::Empty; FIELD_WIDTH];
+        }
+        if self.mirror {
+            field.iter_mut().for_each(|row| row.reverse());
+        }
+        (field, garbage)
+    }
+
+    /// The cell at a row of the field, where row -1 is the garbage row
+    fn cell(field: &[Row], garbage: &Row, x: usize, y: isize) -> Cell {
+        if y < 0 {
+            garbage[x]
+        } else {
+            field[y as usize][x]
+        }
+    }
+}
+
+/// The cells of a page in the order they are written, from the top left to the bottom right of
+/// the garbage row (row -1)
+fn written_cells() -> impl ExactSizeIterator<Item = (usize, isize)> {
+    (0..FIELD_BLOCKS as usize).map(|index| {
+        let x = index % FIELD_WIDTH;
+        let y = FIELD_HEIGHT as isize - 1 - (index / FIELD_WIDTH) as isize;
+        (x, y)
+    })
+}
+
+/// Numbers written as base 64 digits, least significant first
+#[derive(Default)]
+struct Values(Vec<u32>);
+
+impl Values {
+    fn push(&mut self, mut value: u32, digits: u32) {
+        for _ in 0..digits {
+            self.0.push(value % 64);
+            value /= 64;
+        }
+    }
+}
+
+struct Reader<I> {
+    digits: I,
+}
+
+impl<I: Iterator<Item = u32>> Reader<I> {
+    fn poll(&mut self, digits: u32) -> Result<u32, DecodeError> {
+        (0..digits).try_fold(0, |value, digit| {
+            let next = self.digits.next().ok_or(DecodeError::UnexpectedEnd)?;
+            Ok(value + next * 64_u32.pow(digit))
+        })
+    }
+
+    fn is_empty(&mut self) -> bool
+    where
+        I: Clone,
+    {
+        self.digits.clone().next().is_none()
+    }
+}
+
+/// Writes the pages as a fumen
+pub fn encode(pages: &[Page]) -> String {
+    let mut values = Values::default();
+    let mut prev_field = Page::default().field;
+    let mut prev_garbage = [Cell::Empty; FIELD_WIDTH];
+    let mut prev_comment = String::new();
+    // where the count of repeated unchanged fields is, if the last field was unchanged
+    let mut repeat_index: Option<usize> = None;
+
+    for (index, page) in pages.iter().enumerate() {
+        // the changes to the field, as runs of cells changed by the same amount
+        let mut runs: Vec<(u32, u32)> = Vec::new();
+        for (x, y) in written_cells() {
+            let diff = cell_number(Page::cell(&page.field, &page.garbage, x, y)) + 8
+                - cell_number(Page::cell(&prev_field, &prev_garbage, x, y));
+            match runs.last_mut() {
+                Some((last, count)) if *last == diff => *count += 1,
+                _ => runs.push((diff, 0)),
+            }
+        }
+        let unchanged = runs == [(8, FIELD_BLOCKS - 1)];
+        match repeat_index {
+            Some(repeat) if unchanged && values.0[repeat] < 63 => values.0[repeat] += 1,
+            _ => {
+                for (diff, count) in runs {
+                    values.push(diff * FIELD_BLOCKS + count, 2);
+                }
+                repeat_index = unchanged.then(|| {
+                    values.push(0, 1);
+                    values.0.len() - 1
+                });
+            }
+        }
+
+        let comment = page.full_comment();
+        let comment_changed = comment != prev_comment;
+        let placed = page
+            .active
+            .and_then(|mino| Some((mino, fumen_position(&mino)?)));
+        let (piece, direction, position) = match placed {
+            Some((mino, (x, y))) => {
+                let (dx, dy) = written_offset(mino.variant, mino.direction);
+                let (x, y) = (x + dx, y + dy);
+                (
+                    cell_number(mino.variant.into()),
+                    direction_number(mino.direction),
+                    (FIELD_HEIGHT as isize - 1 - y) as u32 * FIELD_WIDTH as u32 + x as u32,
+                )
+            }
+            None => (0, 0, 0),
+        };
+        let flags = [
+            !page.lock,
+            comment_changed,
+            index == 0,
+            page.mirror,
+            page.rise,
+        ];
+        let action = flags.iter().fold(0, |value, &flag| value * 2 + flag as u32);
+        values.push(
+            ((action * FIELD_BLOCKS + position) * 4 + direction) * 8 + piece,
+            3,
+        );
+
+        if comment_changed {
+            let escaped = escape(&comment);
+            let escaped = &escaped.as_bytes()[..escaped.len().min(MAX_COMMENT_LENGTH)];
+            values.push(escaped.len() as u32, 2);
+            for chunk in escaped.chunks(4) {
+                let value = chunk.iter().rev().fold(0, |value, &c| {
+                    let number = COMMENT_TABLE.iter().position(|&t| t == c).unwrap() as u32;
+                    value * (COMMENT_TABLE.len() as u32 + 1) + number
+                });
+                values.push(value, 5);
+            }
+            prev_comment = comment;
+        }
+
+        (prev_field, prev_garbage) = page.next_field();
+    }
+
+    let data = values
+        .0
+        .iter()
+        .map(|&value| TABLE[value as usize] as char)
+        .collect::<String>();
+    // fumen breaks the data up with question marks, first after 42 characters and then every 47
+    let mut out = String::from(PREFIX);
+    let (head, mut tail) = data.split_at(data.len().min(42));
+    out.push_str(head);
+    while !tail.is_empty() {
+        let (chunk, rest) = tail.split_at(tail.len().min(47));
+        out.push('?');
+        out.push_str(chunk);
+        tail = rest;
+    }
+    out
+}
+
+/// Reads the pages of a fumen, which may be given as a whole link to the fumen
+pub fn decode(fumen: &str) -> Result<Vec<Page>, DecodeError> {
+    let (_, data) = fumen
+        .trim()
+        .split_once("115@")
+        .ok_or(DecodeError::UnsupportedVersion)?;
+    let digits = data
+        .chars()
+        .filter(|&c| c != '?')
+        .map(|c| {
+            TABLE
+                .iter()
+                .position(|&t| t as char == c)
+                .map(|value| value as u32)
+                .ok_or(DecodeError::InvalidCharacter(c))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut reader = Reader {
+        digits: digits.into_iter(),
+    };
+
+    let mut pages: Vec<Page> = Vec::new();
+    let mut prev = Page::default();
+    let mut repeats = 0;
+    let mut comment = String::new();
+
+    while !reader.is_empty() {
+        let (mut field, mut garbage) = prev.next_field();
+        if repeats > 0 {
+            repeats -= 1;
+        } else {
+            let mut cells = written_cells();
+            let mut unchanged = false;
+            while cells.len() > 0 {
+                let run = reader.poll(2)?;
+                let (diff, count) = (run / FIELD_BLOCKS, run % FIELD_BLOCKS);
+                unchanged = diff == 8 && count == FIELD_BLOCKS - 1;
+                for _ in 0..=count {
+                    let (x, y) = cells.next().ok_or(DecodeError::InvalidField)?;
+                    let cell = if y < 0 {
+                        &mut garbage[x]
+                    } else {
+                        &mut field[y as usize][x]
+                    };
+                    *cell = (cell_number(*cell) + diff)
+                        .checked_sub(8)
+                        .and_then(number_cell)
+                        .ok_or(DecodeError::InvalidField)?;
+                }
+            }
+            if unchanged {
+                repeats = reader.poll(1)?;
+            }
+        }
+
+        let mut action = reader.poll(3)?;
+        let mut take = |base: u32| {
+            let value = action % base;
+            action /= base;
+            value
+        };
+        let piece = take(8);
+        let direction = number_direction(take(4));
+        let position = take(FIELD_BLOCKS);
+        let [rise, mirror, _colorize, comment_changed, not_lock] = [(); 5].map(|_| take(2) == 1);
+
+        let active = number_cell(piece).and_then(|cell| match cell {
+            Cell::Tetromino(variant) => {
+                let x = (position % FIELD_WIDTH as u32) as isize;
+                let y = FIELD_HEIGHT as isize - 1 - (position / FIELD_WIDTH as u32) as isize;
+                let (dx, dy) = written_offset(variant, direction);
+                Some(mino_at(variant, direction, (x - dx, y - dy)))
+            }
+            _ => None,
+        });
+
+        if comment_changed {
+            let length = reader.poll(2)? as usize;
+            let mut escaped = String::new();
+            for _ in 0..length.div_ceil(4) {
+                let mut value = reader.poll(5)?;
+                for _ in 0..4 {
+                    let base = COMMENT_TABLE.len() as u32 + 1;
+                    let c = COMMENT_TABLE
+                        .get((value % base) as usize)
+                        .ok_or(DecodeError::InvalidComment)?;
+                    escaped.push(*c as char);
+                    value /= base;
+                }
+            }
+            escaped.truncate(length);
+            comment = unescape(&escaped);
+        }
+
+        let (hold, queue, text) =
+            parse_quiz(&comment).unwrap_or((None, Vec::new(), comment.clone()));
+        let page = Page {
+            field,
+            garbage,
+            active,
+            hold,
+            queue,
+            comment: text,
+            lock: !not_lock,
+            rise,
+            mirror,
+        };
+        pages.push(page.clone());
+        prev = page;
+    }
+
+    Ok(pages)
+}
+
+/// Follows a stream of actions to keep track of the board, in the same way as viewtris-player
+#[derive(Default)]
+struct Playback {
+    /// The cells of the board from the bottom up, which grows as high as it needs to
+    matrix: Vec<Row>,
+    active: Option<Mino>,
+    hold: Option<MinoVariant>,
+}
+
+impl Playback {
+    fn row(&mut self, y: usize) -> &mut Row {
+        if self.matrix.len() <= y {
+            self.matrix.resize(y + 1, [Cell::Empty; FIELD_WIDTH]);
+        }
+        &mut self.matrix[y]
+    }
+
+    fn apply(&mut self, action: &ActionKind) {
+        match *action {
+            ActionKind::Garbage { column, height } => {
+                for _ in 0..height {
+                    let mut row = [Cell::Garbage; FIELD_WIDTH];
+                    row[column as usize] = Cell::Empty;
+                    self.matrix.insert(0, row);
+                }
+            }
+            ActionKind::Reposition { piece } => self.active = Some(piece),
+            ActionKind::LineClear { row } => {
+                if (row as usize) < self.matrix.len() {
+                    self.matrix.remove(row as usize);
+                }
+            }
+            ActionKind::Cell {
+                position: (x, y),
+                kind,
+            } => self.row(y as usize)[x as usize] = kind,
+            ActionKind::Hold => {
+                let active = self.active.take().map(|mino| mino.variant);
+                if let Some(held) = std::mem::replace(&mut self.hold, active) {
+                    self.active = Some(held.into());
+                }
+            }
+            ActionKind::Lock { .. }
+            | ActionKind::Attack { .. }
+            | ActionKind::GarbageCancel { .. } => (),
+        }
+    }
+
+    fn page(&self) -> Page {
+        let mut field = self.matrix.clone();
+        field.resize(FIELD_HEIGHT, [Cell::Empty; FIELD_WIDTH]);
+        Page {
+            field,
+            active: self.active,
+            hold: self.hold,
+            ..Page::default()
+        }
+    }
+}
+
+impl Page {
+    /// The board after every action up to and including those on `frame` has happened, with the
+    /// active piece left unlocked. The queue is not known from the actions alone, so it is empty.
+    pub fn from_actions(actions: &[Action], frame: u32) -> Self {
+        let mut playback = Playback::default();
+        actions
+            .iter()
+            .take_while(|action| action.frame <= frame)
+            .for_each(|action| playback.apply(&action.kind));
+        Page {
+            lock: false,
+            ..playback.page()
+        }
+    }
+}
+
+/// Describes a whole game as pages, with a page for each piece placed showing the board before it
+/// locks and where it locks. The last page shows the board at the end of the game.
+pub fn game_pages(actions: &[Action]) -> Vec<Page> {
+    let mut playback = Playback::default();
+    let mut pages = Vec::new();
+    // the cells of the piece being locked, which are placed once the whole piece is known
+    let mut placing: Vec<(isize, isize)> = Vec::new();
+
+    for action in actions {
+        let ActionKind::Cell {
+            position: (x, y),
+            kind: Cell::Tetromino(variant),
+        } = action.kind
+        else {
+            playback.apply(&action.kind);
+            continue;
+        };
+        placing.push((x as isize, y as isize));
+        if placing.len() < 4 {
+            continue;
+        }
+
+        // find where the piece locked from its cells
+        placing.sort_unstable();
+        let directions = playback
+            .active
+            .map(|mino| mino.direction)
+            .into_iter()
+            .chain([
+                Direction::Up,
+                Direction::Right,
+                Direction::Down,
+                Direction::Left,
+            ]);
+        let placed = directions
+            .map(|direction| {
+                let mino = Mino {
+                    variant,
+                    direction,
+                    coord: (0, 0),
+                };
+                align(mino, &placing)
+            })
+            .find(|mino| {
+                let mut cells = mino.position().0;
+                cells.sort_unstable();
+                cells[..] == placing[..]
+            });
+        pages.push(Page {
+            active: placed,
+            ..playback.page()
+        });
+
+        for (x, y) in placing.drain(..) {
+            playback.row(y as usize)[x as usize] = variant.into();
+        }
+    }
+
+    pages.push(Page {
+        lock: false,
+        ..playback.page()
+    });
+    pages
+}
@@ -1,4 +1,5 @@
 pub mod action;
+pub mod fumen;
 pub mod positions;
 pub mod tables;
 pub mod tetromino;
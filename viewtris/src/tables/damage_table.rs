@@ -1,5 +1,7 @@
 use std::{collections::HashMap, ops::Index};
 
+use once_cell::sync::Lazy;
+
 use crate::tetromino::MinoVariant;
 
 #[rustfmt::skip]
@@ -70,3 +72,41 @@ impl Index<&AttackKey> for DamageTable {
         self.get_uncopied(index).unwrap()
     }
 }
+
+/// The attack sent by line clears in TETR.IO, before any bonus for combos, back-to-backs or
+/// perfect clears. Spins of pieces other than T send as much as T-spins.
+pub static TETRIO: Lazy<DamageTable> = Lazy::new(|| {
+    let clear = |lines, spin| Attack {
+        combo: None,
+        b2b: None,
+        piece: None,
+        lines: Some(lines),
+        spin: Some(spin),
+    };
+    let clears = [
+        (Spin::None, [0, 0, 1, 2, 4]),
+        (Spin::Mini, [0, 0, 1, 2, 4]),
+        (Spin::Full, [0, 2, 4, 6, 10]),
+    ];
+
+    DamageTable {
+        general: clears
+            .into_iter()
+            .flat_map(|(spin, attacks)| {
+                attacks
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(lines, attack)| (clear(lines as u8, spin), attack))
+            })
+            .collect(),
+        specific: HashMap::new(),
+    }
+});
+
+/// The attack added by each combo in TETR.IO's classic guideline combo table, starting from the
+/// line clear which starts the combo. Longer combos add as much as the last entry.
+pub const CLASSIC_GUIDELINE_COMBOS: [u16; 11] = [0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5];
+
+/// The attack added by each combo in TETR.IO's modern guideline combo table (see
+/// [CLASSIC_GUIDELINE_COMBOS])
+pub const MODERN_GUIDELINE_COMBOS: [u16; 13] = [0, 1, 1, 2, 2, 2, 3, 3, 3, 3, 3, 3, 4];
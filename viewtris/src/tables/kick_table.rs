@@ -15,7 +15,7 @@ macro_rules! kick_table {
     };
 }
 
-type KickTable = HashMap<Rotation, Vec<(i8, i8)>>;
+pub type KickTable = HashMap<Rotation, Vec<(i8, i8)>>;
 
 pub static SRS_PLUS: Lazy<KickTable> = Lazy::new(|| {
     use MinoVariant::*;
@@ -61,3 +61,35 @@ pub static SRS_PLUS: Lazy<KickTable> = Lazy::new(|| {
         ])
         .collect()
 });
+
+/// The kicks of the Super Rotation System as used by most guideline games, such as jstris. There
+/// are no kicks for 180 degree rotations.
+pub static SRS: Lazy<KickTable> = Lazy::new(|| {
+    use MinoVariant::*;
+
+    [J, L, T, S, Z]
+        .into_iter()
+        .flat_map(|variant| {
+            [
+                kick_table!(variant:0>>1 => [(-1, 0), (-1, 1), (0, -2), (-1, -2)]),
+                kick_table!(variant:1>>0 => [(1, 0), (1, -1), (0, 2), (1, 2)]),
+                kick_table!(variant:1>>2 => [(1, 0), (1, -1), (0, 2), (1, 2)]),
+                kick_table!(variant:2>>1 => [(-1, 0), (-1, 1), (0, -2), (-1, -2)]),
+                kick_table!(variant:2>>3 => [(1, 0), (1, 1), (0, -2), (1, -2)]),
+                kick_table!(variant:3>>2 => [(-1, 0), (-1, -1), (0, 2), (-1, 2)]),
+                kick_table!(variant:3>>0 => [(-1, 0), (-1, -1), (0, 2), (-1, 2)]),
+                kick_table!(variant:0>>3 => [(1, 0), (1, 1), (0, -2), (1, -2)]),
+            ]
+        })
+        .chain([
+            kick_table!(I:0>>1 => [(-2, 0), (1, 0), (-2, -1), (1, 2)]),
+            kick_table!(I:1>>0 => [(2, 0), (-1, 0), (2, 1), (-1, -2)]),
+            kick_table!(I:1>>2 => [(-1, 0), (2, 0), (-1, 2), (2, -1)]),
+            kick_table!(I:2>>1 => [(1, 0), (-2, 0), (1, -2), (-2, 1)]),
+            kick_table!(I:2>>3 => [(2, 0), (-1, 0), (2, 1), (-1, -2)]),
+            kick_table!(I:3>>2 => [(-2, 0), (1, 0), (-2, -1), (1, 2)]),
+            kick_table!(I:3>>0 => [(1, 0), (-2, 0), (1, -2), (-2, 1)]),
+            kick_table!(I:0>>3 => [(-1, 0), (2, 0), (-1, 2), (2, -1)]),
+        ])
+        .collect()
+});
@@ -5,7 +5,10 @@ use tap::Tap;
 
 use crate::{
     positions::Positions,
-    tables::{kick_table::SRS_PLUS, rotation_table::ROTATION_TABLE},
+    tables::{
+        kick_table::{KickTable, SRS_PLUS},
+        rotation_table::ROTATION_TABLE,
+    },
 };
 
 impl From<MinoVariant> for Cell {
@@ -140,12 +143,14 @@ impl Mino {
         ) + self.coord
     }
 
+    /// The kicks to try when rotating the mino, as in tetrio (SRS+)
     pub fn kick(&self, at: Spin) -> Option<&Vec<(i8, i8)>> {
-        SRS_PLUS.get(&Rotation {
-            piece: self.variant,
-            from: self.direction,
-            to: self.direction + at,
-        })
+        self.kick_in(&SRS_PLUS, at)
+    }
+
+    /// The kicks to try when rotating the mino, as given by a kick table
+    pub fn kick_in<'t>(&self, table: &'t KickTable, at: Spin) -> Option<&'t Vec<(i8, i8)>> {
+        table.get(&self.rotation(at))
     }
 
     pub fn rotation(&self, at: Spin) -> Rotation {
@@ -0,0 +1,134 @@
+use viewtris::{
+    fumen::{decode, encode, DecodeError, Page, Row, FIELD_WIDTH},
+    tetromino::{Cell, Direction, Mino, MinoVariant},
+};
+
+/// A row of garbage with a hole in `column`
+fn garbage_row(column: usize) -> Row {
+    let mut row = [Cell::Garbage; FIELD_WIDTH];
+    row[column] = Cell::Empty;
+    row
+}
+
+/// A T lying flat on the floor, taking up the bottom row from column 3 to column 5
+fn flat_t() -> Mino {
+    Mino {
+        variant: MinoVariant::T,
+        direction: Direction::Up,
+        coord: (4, 0),
+    }
+}
+
+#[test]
+fn empty() {
+    assert_eq!(encode(&[Page::default()]), "v115@vhAAgH");
+    assert_eq!(decode("v115@vhAAgH"), Ok(vec![Page::default()]));
+    // fumens are often shared as links
+    assert_eq!(
+        decode("https://harddrop.com/fumen/?v115@vhAAgH"),
+        Ok(vec![Page::default()])
+    );
+}
+
+#[test]
+fn piece() {
+    let page = Page {
+        active: Some(flat_t()),
+        ..Page::default()
+    };
+    assert_eq!(encode(std::slice::from_ref(&page)), "v115@vhAVQJ");
+    assert_eq!(decode("v115@vhAVQJ"), Ok(vec![page]));
+}
+
+#[test]
+fn every_piece() {
+    use MinoVariant::*;
+
+    // every piece in every rotation, resting on a few rows of garbage
+    let mut field = Page::default().field;
+    field[..3].fill(garbage_row(9));
+    let pages = [I, O, T, L, J, S, Z]
+        .into_iter()
+        .flat_map(|variant| {
+            [
+                Direction::Up,
+                Direction::Right,
+                Direction::Down,
+                Direction::Left,
+            ]
+            .map(|direction| {
+                Some(Mino {
+                    variant,
+                    direction,
+                    coord: (4, 8),
+                })
+            })
+        })
+        .chain([None])
+        .map(|active| Page {
+            field: field.clone(),
+            active,
+            lock: false,
+            ..Page::default()
+        })
+        .collect::<Vec<_>>();
+
+    let fumen = encode(&pages);
+    assert_eq!(decode(&fumen), Ok(pages));
+}
+
+#[test]
+fn pages() {
+    let mut field = Page::default().field;
+    field[0] = garbage_row(4);
+    field[1] = [Cell::Tetromino(MinoVariant::L); FIELD_WIDTH];
+    field[1][3..6].fill(Cell::Empty);
+
+    let first = Page {
+        field,
+        garbage: garbage_row(0),
+        active: Some(Mino {
+            variant: MinoVariant::T,
+            direction: Direction::Down,
+            coord: (4, 1),
+        }),
+        hold: Some(MinoVariant::I),
+        queue: vec![MinoVariant::S, MinoVariant::Z, MinoVariant::O],
+        comment: "T-spin double ✓ 100%".to_owned(),
+        rise: true,
+        mirror: true,
+        ..Page::default()
+    };
+
+    // the T clears both lines, the garbage row rises and the field is flipped
+    let mut after = Page::default().field;
+    after[0] = garbage_row(9);
+    let second = Page {
+        field: after,
+        comment: "T-spin double ✓ 100%".to_owned(),
+        lock: false,
+        ..Page::default()
+    };
+
+    // many pages in a row with the same field
+    let mut pages = vec![first, second.clone()];
+    pages.extend((0..100).map(|n| Page {
+        comment: format!("page {n}"),
+        ..second.clone()
+    }));
+
+    let fumen = encode(&pages);
+    // long fumens are broken up with question marks
+    assert!(fumen.contains('?'));
+    assert_eq!(decode(&fumen), Ok(pages));
+}
+
+#[test]
+fn invalid() {
+    assert_eq!(decode("v110@vhAAgH"), Err(DecodeError::UnsupportedVersion));
+    assert_eq!(
+        decode("v115@vh!AgH"),
+        Err(DecodeError::InvalidCharacter('!'))
+    );
+    assert_eq!(decode("v115@vhAAg"), Err(DecodeError::UnexpectedEnd));
+}